@@ -0,0 +1,120 @@
+use crate::tower::{AuraKind, Tower, TowerStats};
+
+/// Strongest aura of a kind reaching a tower (auras of one kind don't stack)
+fn strongest_aura(tower: &Tower, towers: &[Tower], kind: AuraKind) -> u32 {
+    towers
+        .iter()
+        .filter(|t| t.id != tower.id && t.tower_type.aura_kind() == Some(kind))
+        .filter(|t| t.aura_covers(tower.position))
+        .map(|t| t.base_stats.aura_strength)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Combat stats of a tower after applying nearby support auras and its terrain
+pub fn effective_stats(tower: &Tower, towers: &[Tower]) -> TowerStats {
+    let mut stats = tower.base_stats;
+    if tower.tower_type.is_support() {
+        // Auras aren't buffed, but high ground still widens their reach
+        stats.range = stats.range.saturating_add(tower.terrain.range_bonus());
+        return stats;
+    }
+
+    // Terrain bonuses add to the aura of the same kind
+    let damage_bonus = strongest_aura(tower, towers, AuraKind::Damage) as u64
+        + tower.terrain.damage_bonus_percent() as u64;
+    let range_bonus =
+        strongest_aura(tower, towers, AuraKind::Range) + tower.terrain.range_bonus() as u32;
+    let fire_rate_bonus = strongest_aura(tower, towers, AuraKind::FireRate) as u64;
+
+    let damage = (stats.damage as u64) * (100 + damage_bonus) / 100;
+    stats.damage = damage.min(u32::MAX as u64) as u32;
+    stats.range = stats
+        .range
+        .saturating_add(u8::try_from(range_bonus).unwrap_or(u8::MAX));
+    stats.fire_rate_ms = stats.fire_rate_ms.saturating_mul(100) / (100 + fire_rate_bonus);
+    stats
+}
+
+/// Recompute every tower's combat stats from its base stats and auras
+pub fn apply_auras(towers: &mut [Tower]) {
+    let effective: Vec<TowerStats> = towers.iter().map(|t| effective_stats(t, towers)).collect();
+    for (tower, stats) in towers.iter_mut().zip(effective) {
+        tower.stats = stats;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::Terrain;
+    use crate::tower::TowerType;
+
+    #[test]
+    fn test_auras_buff_nearby_towers() {
+        let mut towers = vec![
+            Tower::new(0, (5, 5), TowerType::Arrow, 0),
+            Tower::new(1, (6, 5), TowerType::Banner, 0),
+            Tower::new(2, (5, 6), TowerType::Watchtower, 0),
+            Tower::new(3, (4, 5), TowerType::WarDrum, 0),
+            Tower::new(4, (15, 15), TowerType::Cannon, 0), // Out of every aura
+        ];
+
+        apply_auras(&mut towers);
+
+        let arrow = &towers[0];
+        assert_eq!(arrow.stats.damage, 12); // 10 * 1.2
+        assert_eq!(arrow.stats.range, 4); // 3 + 1
+        assert_eq!(arrow.stats.fire_rate_ms, 434); // 500 / 1.15
+        assert_eq!(arrow.base_stats.damage, 10);
+
+        let cannon = &towers[4];
+        assert_eq!(cannon.stats.damage, cannon.base_stats.damage);
+
+        // Support towers are never buffed
+        assert_eq!(towers[1].stats.aura_strength, 20);
+    }
+
+    #[test]
+    fn test_same_kind_auras_do_not_stack() {
+        let mut banner = Tower::new(1, (6, 5), TowerType::Banner, 0);
+        banner.upgrade().unwrap();
+        let towers = vec![
+            Tower::new(0, (5, 5), TowerType::Arrow, 0),
+            banner,
+            Tower::new(2, (4, 5), TowerType::Banner, 0),
+        ];
+
+        // Only the level-2 banner's 30% applies
+        assert_eq!(effective_stats(&towers[0], &towers).damage, 13);
+    }
+
+    #[test]
+    fn test_terrain_bonuses() {
+        let mut towers = vec![
+            Tower::new(0, (5, 5), TowerType::Arrow, 0),
+            Tower::new(1, (6, 5), TowerType::Banner, 0),
+            Tower::new(2, (9, 9), TowerType::Arrow, 0),
+        ];
+        towers[0].terrain = Terrain::Rune;
+        towers[2].terrain = Terrain::HighGround;
+
+        apply_auras(&mut towers);
+        assert_eq!(towers[0].stats.damage, 14); // 10 * (1 + 0.2 + 0.25)
+        assert_eq!(towers[0].stats.range, 3);
+        assert_eq!(towers[2].stats.damage, 10);
+        assert_eq!(towers[2].stats.range, 4);
+
+        // A banner on high ground reaches a tower one tile past its base range
+        let mut towers = vec![
+            Tower::new(0, (12, 5), TowerType::Banner, 0),
+            Tower::new(1, (15, 5), TowerType::Arrow, 0),
+        ];
+        apply_auras(&mut towers);
+        assert_eq!(towers[1].stats.damage, 10);
+        towers[0].terrain = Terrain::HighGround;
+        apply_auras(&mut towers);
+        assert_eq!(towers[0].stats.range, 3);
+        assert_eq!(towers[1].stats.damage, 12);
+    }
+}
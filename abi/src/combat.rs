@@ -1,55 +1,119 @@
-use crate::enemy::Enemy;
-use crate::tower::{Tower, TowerType};
-
-/// Find the best enemy target for a tower
-pub fn find_target<'a>(tower: &Tower, enemies: &'a [Enemy]) -> Option<&'a Enemy> {
-    enemies
-        .iter()
-        .filter(|e| e.is_alive() && tower.in_range(e.position))
-        .max_by_key(|e| e.path_index) // Target enemy furthest along the path
+use crate::enemy::{Enemy, EnemyType, MovementClass};
+use crate::fixed::{
+    apply_permille, distance, distance_squared, tile_pos_to_milli, within_radius,
+    MICROS_PER_SECOND, PERMILLE,
+};
+use crate::game::Grid;
+use crate::pathfinding::Route;
+use crate::status::{StatusEffect, StatusKind};
+use crate::terrain::{terrain_at, TerrainMap};
+use crate::tower::{Specialization, TargetPriority, Tower, TowerType};
+
+/// Find the best enemy target for a tower, following its target priority
+pub fn find_target<'a>(tower: &Tower, enemies: &'a [Enemy], grid: &Grid) -> Option<&'a Enemy> {
+    select_target(tower, enemies.iter(), grid)
 }
 
 /// Find the best enemy target by ID for a tower
-pub fn find_target_id(tower: &Tower, enemies: &[(u64, Enemy)]) -> Option<u64> {
-    enemies
-        .iter()
-        .filter(|(_, e)| e.is_alive() && tower.in_range(e.position))
-        .max_by_key(|(_, e)| e.path_index)
-        .map(|(id, _)| *id)
+pub fn find_target_id(tower: &Tower, enemies: &[(u64, Enemy)], grid: &Grid) -> Option<u64> {
+    select_target(tower, enemies.iter().map(|(_, e)| e), grid).map(|e| e.id)
 }
 
-/// Calculate damage to apply to an enemy from a tower
-pub fn calculate_damage(tower: &Tower, enemy: &Enemy) -> u32 {
-    // Base damage from tower
-    let mut damage = tower.stats.damage;
-
-    // Tower-specific effects
-    match tower.tower_type {
-        TowerType::Ice => {
-            // Ice towers do less damage but apply slow
-            damage
-        }
-        TowerType::Magic => {
-            // Magic towers do bonus damage to tanks
-            if matches!(enemy.enemy_type, crate::enemy::EnemyType::Tank) {
-                damage = damage.saturating_mul(2);
+fn select_target<'a>(
+    tower: &Tower,
+    enemies: impl Iterator<Item = &'a Enemy>,
+    grid: &Grid,
+) -> Option<&'a Enemy> {
+    let candidates = enemies.filter(|e| {
+        e.is_alive() && tower.in_range(e.position) && tower.tower_type.can_target(e.movement)
+    });
+    let to_base = |e: &&Enemy| remaining_distance(e, grid);
+
+    match tower.target_priority {
+        TargetPriority::First => candidates.min_by_key(to_base),
+        TargetPriority::Last => candidates.max_by_key(to_base),
+        TargetPriority::Strongest => candidates.max_by_key(|e| e.health),
+        TargetPriority::Weakest => candidates.min_by_key(|e| e.health),
+        TargetPriority::Closest => candidates.min_by_key(|e| tower.distance_to(e.position)),
+        TargetPriority::Fastest => candidates.max_by_key(|e| e.effective_speed()),
+        TargetPriority::BossOnly => candidates
+            .filter(|e| e.enemy_type == EnemyType::Boss)
+            .min_by_key(to_base),
+    }
+}
+
+/// How far an enemy still has to go to reach the base along its route, in milli-tiles.
+/// A cut-off enemy on a flow field is as far away as it gets.
+pub fn remaining_distance(enemy: &Enemy, grid: &Grid) -> u32 {
+    match grid.route(enemy.movement, enemy.lane) {
+        // The rest of the path past the next waypoint is looked up, not walked
+        Route::Waypoints(path) => match path.get(enemy.path_index) {
+            Some(next) => distance(enemy.position, tile_pos_to_milli(*next)).saturating_add(
+                grid.lane(enemy.lane)
+                    .length_from(enemy.movement, enemy.path_index),
+            ),
+            None => 0,
+        },
+        Route::Flow(field) => {
+            if enemy.position == tile_pos_to_milli(field.goal.to_tuple()) {
+                return 0;
             }
-            damage
-        }
-        TowerType::Lightning => {
-            // Lightning chains to multiple enemies (handled separately)
-            damage
+            field
+                .next_tile_from(enemy.position)
+                .and_then(|next| {
+                    let leg = distance(enemy.position, tile_pos_to_milli(next.to_tuple()));
+                    field.distance(next).map(|rest| rest.saturating_add(leg))
+                })
+                .unwrap_or(u32::MAX)
         }
-        _ => damage,
     }
 }
 
+/// Calculate damage to apply to an enemy from a tower
+pub fn calculate_damage(tower: &Tower, enemy: &Enemy) -> u32 {
+    if enemy.is_immune() {
+        return 0;
+    }
+
+    let damage_type = tower.stats.damage_type;
+
+    // Resistance matrix: per-mille multiplier for this enemy and damage type
+    let resistance = enemy.resistances.multiplier(damage_type);
+    let mut damage = apply_permille(tower.stats.damage, resistance);
+
+    // Armor blunts physical and explosive hits, but every hit does at least 1
+    if damage_type.is_reduced_by_armor() {
+        damage = damage.saturating_sub(enemy.armor).max(1);
+    }
+
+    // Armor shred raises damage from every hit
+    apply_permille(damage, enemy.damage_taken_permille())
+}
+
 /// Apply special effects based on tower type
 pub fn apply_tower_effects(tower: &Tower, enemy: &mut Enemy) {
-    match tower.tower_type {
-        TowerType::Ice => {
-            // Ice towers slow enemies by 30%
-            enemy.apply_slow(0.7);
+    let effect = |kind, magnitude, duration_micros| {
+        StatusEffect::new(kind, magnitude, duration_micros, tower.id)
+    };
+
+    match (tower.tower_type, tower.specialization) {
+        (TowerType::Ice, Some(Specialization::Glacier)) => {
+            // Glacier slows by 50%
+            enemy.apply_status(effect(StatusKind::Slow, 500, 2_000_000));
+        }
+        (TowerType::Ice, specialization) => {
+            // Ice towers slow enemies by 30% for two seconds
+            enemy.apply_status(effect(StatusKind::Slow, 300, 2_000_000));
+            if specialization == Some(Specialization::Frostbite) {
+                enemy.apply_status(effect(StatusKind::Stun, 0, 300_000));
+            }
+        }
+        (_, Some(Specialization::Hex)) => {
+            // Each hit shreds 15% armor for three seconds
+            enemy.apply_status(effect(StatusKind::ArmorShred, 150, 3_000_000));
+        }
+        (_, Some(Specialization::Overload)) => {
+            enemy.apply_status(effect(StatusKind::Stun, 0, 400_000));
         }
         _ => {
             // Most towers don't apply special effects
@@ -57,92 +121,184 @@ pub fn apply_tower_effects(tower: &Tower, enemy: &mut Enemy) {
     }
 }
 
-/// Update enemy position along the path
-pub fn update_enemy_position(enemy: &mut Enemy, path: &[(u8, u8)], delta_time_micros: u64) {
-    if enemy.path_index >= path.len() {
-        return; // Enemy reached the end
+/// Update enemy position along its route. Ground enemies move at the speed of
+/// the terrain they are stepping onto. Returns true once the enemy reaches the end.
+pub fn update_enemy_position(
+    enemy: &mut Enemy,
+    route: Route,
+    terrain: &TerrainMap,
+    delta_time_micros: u64,
+) -> bool {
+    let mut movement = Movement {
+        terrain: (enemy.movement == MovementClass::Ground).then_some(terrain),
+        remaining_micros: delta_time_micros,
+    };
+
+    match route {
+        Route::Waypoints(path) => {
+            while enemy.path_index < path.len() {
+                if !movement.step_towards(enemy, path[enemy.path_index]) {
+                    return false;
+                }
+            }
+            true
+        }
+        Route::Flow(field) => {
+            // Walk downhill tile by tile; `path_index` counts the tiles entered so far
+            let goal = tile_pos_to_milli(field.goal.to_tuple());
+            while enemy.position != goal {
+                // A cut-off enemy waits in place until a tile is freed
+                let Some(next) = field.next_tile_from(enemy.position) else {
+                    return false;
+                };
+                if !movement.step_towards(enemy, next.to_tuple()) {
+                    return false;
+                }
+            }
+            true
+        }
     }
+}
 
-    let delta_seconds = (delta_time_micros as f64) / 1_000_000.0;
-    let distance_to_move = enemy.effective_speed() * (delta_seconds as f32);
-
-    let mut remaining_distance = distance_to_move;
+/// Time left to move this step, and the terrain that sets the pace (None for flyers)
+struct Movement<'a> {
+    terrain: Option<&'a TerrainMap>,
+    remaining_micros: u64,
+}
 
-    while remaining_distance > 0.0 && enemy.path_index < path.len() {
-        let target = path[enemy.path_index];
-        let target_pos = (target.0 as f32, target.1 as f32);
+impl Movement<'_> {
+    /// Move towards a waypoint tile, returning true (and advancing `path_index`) if it is reached
+    fn step_towards(&mut self, enemy: &mut Enemy, tile: (u8, u8)) -> bool {
+        let terrain_speed = self
+            .terrain
+            .map_or(PERMILLE, |t| terrain_at(t, tile).speed_permille());
+        // Speed is milli-tiles per second
+        let speed = apply_permille(enemy.effective_speed(), terrain_speed) as u64;
+        let reach = speed.saturating_mul(self.remaining_micros) / MICROS_PER_SECOND;
 
-        let dx = target_pos.0 - enemy.position.0;
-        let dy = target_pos.1 - enemy.position.1;
-        let distance = (dx * dx + dy * dy).sqrt();
+        let target_pos = tile_pos_to_milli(tile);
+        let waypoint_distance = distance(enemy.position, target_pos) as u64;
 
-        if distance <= remaining_distance {
-            // Reached this waypoint, move to next
+        if waypoint_distance <= reach {
             enemy.position = target_pos;
             enemy.path_index += 1;
-            remaining_distance -= distance;
-        } else {
-            // Move towards waypoint
-            let ratio = remaining_distance / distance;
-            enemy.position.0 += dx * ratio;
-            enemy.position.1 += dy * ratio;
-            remaining_distance = 0.0;
+            let spent = (waypoint_distance * MICROS_PER_SECOND)
+                .checked_div(speed)
+                .unwrap_or(0);
+            self.remaining_micros = self.remaining_micros.saturating_sub(spent);
+            return true;
         }
-    }
 
-    // Reset slow effect after movement (will be reapplied by towers)
-    enemy.reset_slow();
+        // Move towards waypoint (truncating toward zero keeps this deterministic)
+        let dx = (target_pos.0 - enemy.position.0) as i64;
+        let dy = (target_pos.1 - enemy.position.1) as i64;
+        let moved = reach as i64;
+        enemy.position.0 += (dx * moved / waypoint_distance as i64) as i32;
+        enemy.position.1 += (dy * moved / waypoint_distance as i64) as i32;
+        false
+    }
 }
 
-/// Find enemies in range of a tower for chain lightning
+/// Find the enemies a chain jumps to, each nearest to the previous hit
 pub fn find_chain_targets<'a>(
     tower: &Tower,
     primary_target: &Enemy,
     enemies: &'a [Enemy],
     max_chains: usize,
 ) -> Vec<&'a Enemy> {
-    let mut targets = Vec::new();
-
-    for enemy in enemies {
-        if targets.len() >= max_chains {
-            break;
-        }
-
-        if enemy.id != primary_target.id && enemy.is_alive() && tower.in_range(enemy.position) {
-            targets.push(enemy);
+    let mut targets: Vec<&Enemy> = Vec::new();
+    let mut last_position = primary_target.position;
+
+    while targets.len() < max_chains {
+        let next = enemies
+            .iter()
+            .filter(|e| {
+                e.id != primary_target.id
+                    && e.is_alive()
+                    && !targets.iter().any(|t| t.id == e.id)
+                    && tower.tower_type.can_target(e.movement)
+                    && within_radius(e.position, last_position, tower.stats.chain_range)
+            })
+            .min_by_key(|e| (distance_squared(e.position, last_position), e.id));
+
+        match next {
+            Some(enemy) => {
+                last_position = enemy.position;
+                targets.push(enemy);
+            }
+            None => break,
         }
     }
 
     targets
 }
 
-/// Find enemies in AoE range for magic towers
-pub fn find_aoe_targets<'a>(
-    center: (f32, f32),
-    radius: f32,
-    enemies: &'a [Enemy],
-) -> Vec<&'a Enemy> {
+/// Find enemies in AoE range (center and radius in milli-tiles)
+pub fn find_aoe_targets(center: (i32, i32), radius: u32, enemies: &[Enemy]) -> Vec<&Enemy> {
     enemies
         .iter()
-        .filter(|e| {
-            if !e.is_alive() {
-                return false;
-            }
-            let dx = e.position.0 - center.0;
-            let dy = e.position.1 - center.1;
-            let distance = (dx * dx + dy * dy).sqrt();
-            distance <= radius
-        })
+        .filter(|e| e.is_alive() && within_radius(e.position, center, radius))
         .collect()
 }
 
+/// Damage dealt to one enemy by a single tower shot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub enemy_id: u64,
+    pub damage: u32,
+}
+
+/// Resolve every hit of one shot: the primary target, chain jumps and splash
+pub fn resolve_hits(tower: &Tower, primary_target: &Enemy, enemies: &[Enemy]) -> Vec<Hit> {
+    let mut hits = vec![Hit {
+        enemy_id: primary_target.id,
+        damage: calculate_damage(tower, primary_target),
+    }];
+
+    // Each jump keeps chain_falloff_percent of the previous jump's damage
+    let mut percent = 100u64;
+    let max_chains = tower.stats.chain_targets as usize;
+    for enemy in find_chain_targets(tower, primary_target, enemies, max_chains) {
+        percent = percent * tower.stats.chain_falloff_percent as u64 / 100;
+        let damage = (calculate_damage(tower, enemy) as u64 * percent / 100) as u32;
+        if damage > 0 {
+            hits.push(Hit {
+                enemy_id: enemy.id,
+                damage,
+            });
+        }
+    }
+
+    if tower.stats.splash_radius > 0 {
+        for enemy in find_aoe_targets(primary_target.position, tower.stats.splash_radius, enemies) {
+            // Splash lands on the ground, and never hits an enemy twice
+            let already_hit = hits.iter().any(|h| h.enemy_id == enemy.id);
+            if already_hit || !tower.tower_type.can_target(enemy.movement) {
+                continue;
+            }
+            let splash_percent = tower.stats.splash_percent as u64;
+            let damage = (calculate_damage(tower, enemy) as u64 * splash_percent / 100) as u32;
+            if damage > 0 {
+                hits.push(Hit {
+                    enemy_id: enemy.id,
+                    damage,
+                });
+            }
+        }
+    }
+
+    hits
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::enemy::EnemyType;
+    use crate::pathfinding::{FlowField, Position};
+    use crate::terrain::Terrain;
 
     #[test]
     fn test_find_target() {
+        let grid = Grid::new();
         let tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
 
         let enemies = vec![
@@ -159,7 +315,7 @@ mod tests {
             },
         ];
 
-        let target = find_target(&tower, &enemies);
+        let target = find_target(&tower, &enemies, &grid);
         assert!(target.is_some());
         // Should target enemy 1 (furthest along path within range)
         assert_eq!(target.unwrap().id, 1);
@@ -167,6 +323,7 @@ mod tests {
 
     #[test]
     fn test_find_target_none() {
+        let grid = Grid::new();
         let tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
 
         let enemies = vec![
@@ -177,10 +334,92 @@ mod tests {
             },
         ];
 
-        let target = find_target(&tower, &enemies);
+        let target = find_target(&tower, &enemies, &grid);
         assert!(target.is_none());
     }
 
+    #[test]
+    fn test_find_target_priorities() {
+        let grid = Grid::new();
+        let mut tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
+
+        let enemies = vec![
+            {
+                let mut e = Enemy::new(0, EnemyType::BasicScout, 1, (5, 7));
+                e.path_index = 2;
+                e
+            },
+            {
+                let mut e = Enemy::new(1, EnemyType::Tank, 1, (6, 5));
+                e.path_index = 1;
+                e
+            },
+            {
+                let mut e = Enemy::new(2, EnemyType::FastRunner, 1, (7, 6));
+                e.path_index = 4;
+                e.health = 3;
+                e
+            },
+        ];
+
+        let target_for = |tower: &Tower| find_target(tower, &enemies, &grid).map(|e| e.id);
+        let expected = [
+            (TargetPriority::First, Some(2)),
+            (TargetPriority::Last, Some(1)),
+            (TargetPriority::Strongest, Some(1)),
+            (TargetPriority::Weakest, Some(2)),
+            (TargetPriority::Closest, Some(1)),
+            (TargetPriority::Fastest, Some(2)),
+            (TargetPriority::BossOnly, None),
+        ];
+        for (priority, id) in expected {
+            tower.target_priority = priority;
+            assert_eq!(target_for(&tower), id, "{:?}", priority);
+        }
+
+        let with_ids: Vec<(u64, Enemy)> = enemies.iter().map(|e| (e.id, e.clone())).collect();
+        tower.target_priority = TargetPriority::Strongest;
+        assert_eq!(find_target_id(&tower, &with_ids, &grid), Some(1));
+    }
+
+    #[test]
+    fn test_first_follows_route_to_base() {
+        let grid = Grid::open_field();
+        let mut tower = Tower::new(0, (10, 10), TowerType::Arrow, 0);
+        let at = |id, enemy_type, tile, path_index| {
+            let mut e = Enemy::new(id, enemy_type, 1, tile);
+            e.path_index = path_index;
+            e
+        };
+
+        // On a flow field `path_index` only counts tiles walked, not progress
+        let ground = vec![
+            at(0, EnemyType::BasicScout, (9, 10), 6),
+            at(1, EnemyType::BasicScout, (11, 11), 0),
+        ];
+        // Flyers share the two-point air route, so `path_index` ties
+        let air = vec![
+            at(3, EnemyType::Flyer, (11, 10), 1),
+            at(2, EnemyType::Flyer, (9, 11), 1),
+        ];
+        for (enemies, first, last) in [(&ground, 1, 0), (&air, 3, 2)] {
+            tower.target_priority = TargetPriority::First;
+            assert_eq!(find_target(&tower, enemies, &grid).unwrap().id, first);
+            tower.target_priority = TargetPriority::Last;
+            assert_eq!(find_target(&tower, enemies, &grid).unwrap().id, last);
+        }
+
+        let at_spawn = at(4, EnemyType::Flyer, (0, 10), 0);
+        assert_eq!(remaining_distance(&at_spawn, &grid), 19_416);
+
+        // The classic path is 7 + 5 + 7 + 9 + 5 tiles from the spawn
+        let classic = Grid::new();
+        let walker = at(5, EnemyType::BasicScout, (0, 10), 0);
+        assert_eq!(remaining_distance(&walker, &classic), 33_000);
+        let climbing = at(6, EnemyType::BasicScout, (7, 7), 10);
+        assert_eq!(remaining_distance(&climbing, &classic), 23_000);
+    }
+
     #[test]
     fn test_calculate_damage() {
         let arrow_tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
@@ -199,42 +438,151 @@ mod tests {
         assert_eq!(calculate_damage(&magic_tower, &tank), 30); // 15 * 2
     }
 
+    #[test]
+    fn test_calculate_damage_resistances() {
+        let arrow_tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
+        let ice_tower = Tower::new(1, (5, 5), TowerType::Ice, 0);
+        let cannon_tower = Tower::new(2, (5, 5), TowerType::Cannon, 0);
+
+        let tank = Enemy::new(0, EnemyType::Tank, 1, (5, 5));
+        let runner = Enemy::new(1, EnemyType::FastRunner, 1, (5, 5));
+
+        // Arrow vs tank: 10 * 0.7 = 7, minus 5 armor
+        assert_eq!(calculate_damage(&arrow_tower, &tank), 2);
+        // Cannon vs tank: 50 * 1.2 = 60, minus 5 armor
+        assert_eq!(calculate_damage(&cannon_tower, &tank), 55);
+        // Ice ignores armor and runners are weak to it: 5 * 1.25 = 6
+        assert_eq!(calculate_damage(&ice_tower, &runner), 6);
+
+        // Armor can never reduce a hit below 1
+        let mut armored = tank.clone();
+        armored.armor = 100;
+        assert_eq!(calculate_damage(&arrow_tower, &armored), 1);
+    }
+
     #[test]
     fn test_apply_tower_effects() {
         let ice_tower = Tower::new(0, (5, 5), TowerType::Ice, 0);
         let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (5, 5));
 
-        assert_eq!(enemy.slow_multiplier, 1.0);
+        assert_eq!(enemy.slow_multiplier(), 1000);
 
         apply_tower_effects(&ice_tower, &mut enemy);
 
-        assert_eq!(enemy.slow_multiplier, 0.7);
+        assert_eq!(enemy.slow_multiplier(), 700);
+
+        // The slow outlives the next movement step
+        let path = vec![(0, 0), (10, 0)];
+        update_enemy_position(
+            &mut enemy,
+            Route::Waypoints(&path),
+            &TerrainMap::new(),
+            100_000,
+        );
+        assert_eq!(enemy.slow_multiplier(), 700);
+    }
+
+    #[test]
+    fn test_specialization_effects() {
+        let mut hex = Tower::new(0, (5, 5), TowerType::Magic, 0);
+        hex.level = 3;
+        hex.specialize(Specialization::Hex).unwrap();
+        let mut frostbite = Tower::new(1, (5, 5), TowerType::Ice, 0);
+        frostbite.level = 3;
+        frostbite.specialize(Specialization::Frostbite).unwrap();
+
+        let mut enemy = Enemy::new(0, EnemyType::Tank, 1, (5, 5));
+        apply_tower_effects(&hex, &mut enemy);
+        apply_tower_effects(&hex, &mut enemy);
+        assert_eq!(enemy.damage_taken_permille(), 1300);
+
+        apply_tower_effects(&frostbite, &mut enemy);
+        assert!(enemy.is_stunned());
+        assert_eq!(enemy.slow_multiplier(), 700);
     }
 
     #[test]
     fn test_update_enemy_position() {
         let path = vec![(0, 0), (5, 0), (5, 5), (10, 5)];
         let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
-        enemy.speed = 1.0; // 1 tile per second
+        enemy.speed = 1000; // 1 tile per second
 
         // Move for 1 second (1,000,000 microseconds)
-        update_enemy_position(&mut enemy, &path, 1_000_000);
-
-        // Should have moved approximately 1 tile
-        assert!(enemy.position.0 > 0.0);
+        update_enemy_position(
+            &mut enemy,
+            Route::Waypoints(&path),
+            &TerrainMap::new(),
+            1_000_000,
+        );
+
+        // Should have moved exactly 1 tile
+        assert_eq!(enemy.position, (1000, 0));
     }
 
     #[test]
     fn test_update_enemy_position_reaching_waypoint() {
         let path = vec![(0, 0), (1, 0), (2, 0)];
         let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
-        enemy.speed = 2.0; // 2 tiles per second
+        enemy.speed = 1500; // 1.5 tiles per second
 
         // Move for 1 second
-        update_enemy_position(&mut enemy, &path, 1_000_000);
-
-        // Should have reached second waypoint (index 2)
+        update_enemy_position(
+            &mut enemy,
+            Route::Waypoints(&path),
+            &TerrainMap::new(),
+            1_000_000,
+        );
+
+        // Spawn waypoint is reached immediately, then (1, 0); now heading to index 2
         assert_eq!(enemy.path_index, 2);
+        assert_eq!(enemy.position, (1500, 0));
+    }
+
+    #[test]
+    fn test_terrain_sets_ground_speed() {
+        let path = vec![(0, 0), (1, 0), (2, 0), (3, 0)];
+        let mut terrain = TerrainMap::new();
+        terrain.insert((1, 0), Terrain::Mud);
+        terrain.insert((2, 0), Terrain::Road);
+
+        let mut scout = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
+        scout.speed = 1000;
+        // 2s wading into the mud at half speed, then 0.5s at 1.5x down the road
+        update_enemy_position(&mut scout, Route::Waypoints(&path), &terrain, 2_500_000);
+        assert_eq!(scout.position, (1750, 0));
+
+        // Flyers ignore the ground
+        let mut flyer = Enemy::new(1, EnemyType::Flyer, 1, (0, 0));
+        flyer.speed = 1000;
+        update_enemy_position(&mut flyer, Route::Waypoints(&path), &terrain, 2_500_000);
+        assert_eq!(flyer.position, (2500, 0));
+    }
+
+    #[test]
+    fn test_update_enemy_position_on_flow_field() {
+        let blocked = [Position::new(1, 0)].into_iter().collect();
+        let field = FlowField::build(Position::new(2, 0), 5, 5, &blocked);
+        let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
+        enemy.speed = 1000;
+
+        // Down and around the blocked (1, 0)
+        assert!(!update_enemy_position(
+            &mut enemy,
+            Route::Flow(&field),
+            &TerrainMap::new(),
+            1_500_000
+        ));
+        assert_eq!(enemy.position, (500, 1000));
+        assert_eq!(enemy.path_index, 1);
+
+        assert!(update_enemy_position(
+            &mut enemy,
+            Route::Flow(&field),
+            &TerrainMap::new(),
+            3_000_000
+        ));
+        assert_eq!(enemy.position, (2000, 0));
+        assert_eq!(enemy.path_index, 4);
     }
 
     #[test]
@@ -258,8 +606,8 @@ mod tests {
 
     #[test]
     fn test_find_aoe_targets() {
-        let center = (5.0, 5.0);
-        let radius = 2.0;
+        let center = (5000, 5000);
+        let radius = 2000;
 
         let enemies = vec![
             Enemy::new(0, EnemyType::BasicScout, 1, (5, 5)), // At center
@@ -273,4 +621,83 @@ mod tests {
         assert!(targets.iter().any(|e| e.id == 0));
         assert!(targets.iter().any(|e| e.id == 1));
     }
+
+    #[test]
+    fn test_chain_targets_jump_from_last_hit() {
+        let tower = Tower::new(0, (5, 5), TowerType::Lightning, 0);
+        let primary = Enemy::new(0, EnemyType::BasicScout, 1, (5, 5));
+
+        // A line of enemies 1.5 tiles apart; only hop-by-hop reaches the far ones
+        let enemies = vec![
+            Enemy::new(0, EnemyType::BasicScout, 1, (5, 5)),
+            Enemy::new(1, EnemyType::BasicScout, 1, (8, 5)),
+            Enemy::new(2, EnemyType::BasicScout, 1, (7, 5)),
+            Enemy::new(3, EnemyType::BasicScout, 1, (6, 5)),
+            Enemy::new(4, EnemyType::BasicScout, 1, (12, 5)), // Gap too wide
+        ];
+
+        let targets = find_chain_targets(&tower, &primary, &enemies, 5);
+        let ids: Vec<u64> = targets.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_resolve_hits_chain_falloff() {
+        let tower = Tower::new(0, (5, 5), TowerType::Lightning, 0);
+        let enemies = vec![
+            Enemy::new(0, EnemyType::BasicScout, 1, (5, 5)),
+            Enemy::new(1, EnemyType::BasicScout, 1, (6, 5)),
+            Enemy::new(2, EnemyType::BasicScout, 1, (7, 5)),
+            Enemy::new(3, EnemyType::BasicScout, 1, (8, 5)),
+            Enemy::new(4, EnemyType::BasicScout, 1, (9, 5)),
+        ];
+
+        let hits: Vec<(u64, u32)> = resolve_hits(&tower, &enemies[0], &enemies)
+            .iter()
+            .map(|h| (h.enemy_id, h.damage))
+            .collect();
+        // 30, then 70% per jump: 21, 14 (14.7), 10 (10.29); max 3 jumps
+        assert_eq!(hits, vec![(0, 30), (1, 21), (2, 14), (3, 10)]);
+    }
+
+    #[test]
+    fn test_resolve_hits_splash() {
+        let tower = Tower::new(0, (5, 5), TowerType::Cannon, 0);
+        let enemies = vec![
+            Enemy::new(0, EnemyType::BasicScout, 1, (6, 5)),
+            Enemy::new(1, EnemyType::BasicScout, 1, (7, 5)), // 1 tile from primary
+            Enemy::new(2, EnemyType::BasicScout, 1, (9, 5)), // Outside splash
+        ];
+
+        let hits: Vec<(u64, u32)> = resolve_hits(&tower, &enemies[0], &enemies)
+            .iter()
+            .map(|h| (h.enemy_id, h.damage))
+            .collect();
+        assert_eq!(hits, vec![(0, 50), (1, 25)]);
+
+        // Single-target towers only hit the primary
+        let arrow = Tower::new(1, (5, 5), TowerType::Arrow, 0);
+        assert_eq!(resolve_hits(&arrow, &enemies[0], &enemies).len(), 1);
+    }
+
+    #[test]
+    fn test_anti_air_targeting() {
+        let grid = Grid::new();
+        let enemies = vec![
+            Enemy::new(0, EnemyType::Flyer, 1, (6, 5)),
+            Enemy::new(1, EnemyType::BasicScout, 1, (7, 5)),
+        ];
+
+        // Cannons skip the flyer and shell the walker instead
+        let cannon = Tower::new(0, (5, 5), TowerType::Cannon, 0);
+        assert_eq!(find_target(&cannon, &enemies, &grid).unwrap().id, 1);
+        let splash = resolve_hits(&cannon, &enemies[1], &enemies);
+        assert!(splash.iter().all(|h| h.enemy_id != 0));
+
+        let arrow = Tower::new(1, (5, 5), TowerType::Arrow, 0);
+        assert_eq!(find_target(&arrow, &enemies[..1], &grid).unwrap().id, 0);
+
+        let ice = Tower::new(2, (5, 5), TowerType::Ice, 0);
+        assert!(find_target(&ice, &enemies[..1], &grid).is_none());
+    }
 }
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// Difficulty preset picked when creating a game
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Difficulty {
+    /// Slower health growth, richer kills, more gold and lives
+    Easy,
+    #[default]
+    Normal,
+    /// Faster health growth, leaner kills, less gold and lives
+    Hard,
+    /// Hard, but more so
+    Nightmare,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Self::Easy, Self::Normal, Self::Hard, Self::Nightmare];
+
+    /// Enemy health gained per wave, in percent of base health
+    pub fn health_growth_percent(&self) -> u32 {
+        match self {
+            Self::Easy => 7,
+            Self::Normal => 10,
+            Self::Hard => 14,
+            Self::Nightmare => 20,
+        }
+    }
+
+    /// Kill rewards in percent of Normal
+    pub fn reward_percent(&self) -> u32 {
+        match self {
+            Self::Easy => 125,
+            Self::Normal => 100,
+            Self::Hard => 85,
+            Self::Nightmare => 70,
+        }
+    }
+
+    pub fn starting_gold(&self) -> u64 {
+        match self {
+            Self::Easy => 750,
+            Self::Normal => 500,
+            Self::Hard => 400,
+            Self::Nightmare => 300,
+        }
+    }
+
+    /// Lives: how many leaks the base survives
+    pub fn base_health(&self) -> u32 {
+        match self {
+            Self::Easy => 30,
+            Self::Normal => 20,
+            Self::Hard => 15,
+            Self::Nightmare => 10,
+        }
+    }
+
+    /// Scale a Normal kill reward to this difficulty
+    pub fn scale_reward(&self, reward: u64) -> u64 {
+        reward.saturating_mul(self.reward_percent() as u64) / 100
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::EnemyType;
+
+    #[test]
+    fn test_presets_get_harder() {
+        for pair in Difficulty::ALL.windows(2) {
+            let (easier, harder) = (pair[0], pair[1]);
+            assert!(easier.health_growth_percent() < harder.health_growth_percent());
+            assert!(easier.reward_percent() > harder.reward_percent());
+            assert!(easier.starting_gold() > harder.starting_gold());
+            assert!(easier.base_health() > harder.base_health());
+
+            let wave = 20;
+            let easy_stats = EnemyType::Tank.stats(wave, easier);
+            let hard_stats = EnemyType::Tank.stats(wave, harder);
+            assert!(easy_stats.health < hard_stats.health);
+            assert!(easy_stats.gold_reward > hard_stats.gold_reward);
+        }
+    }
+
+    #[test]
+    fn test_normal_matches_base_stats() {
+        for wave in [0, 1, 10, 150] {
+            let normal = EnemyType::Boss.stats(wave, Difficulty::Normal);
+            let base = EnemyType::Boss.base_stats(wave);
+            assert_eq!(normal.health, base.health);
+            assert_eq!(normal.gold_reward, base.gold_reward);
+        }
+        assert_eq!(Difficulty::default(), Difficulty::Normal);
+        assert_eq!(Difficulty::Nightmare.scale_reward(200), 140);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::difficulty::Difficulty;
 use crate::enemy::EnemyType;
 use crate::tower::TowerType;
 use serde::{Deserialize, Serialize};
@@ -6,7 +7,7 @@ use serde::{Deserialize, Serialize};
 pub struct EconomyConfig {
     pub starting_gold: u64,
     pub wave_completion_bonus: u64,
-    pub tower_sell_ratio: f64, // Percentage of total investment returned when selling
+    pub tower_sell_percent: u64, // Percentage of total investment returned when selling
 }
 
 impl Default for EconomyConfig {
@@ -14,24 +15,34 @@ impl Default for EconomyConfig {
         Self {
             starting_gold: 500,
             wave_completion_bonus: 50,
-            tower_sell_ratio: 0.7, // 70% return
+            tower_sell_percent: 70, // 70% return
         }
     }
 }
 
 /// Calculate gold reward for killing an enemy
-pub fn calculate_kill_reward(enemy_type: EnemyType, wave_number: u32) -> u64 {
+pub fn calculate_kill_reward(
+    enemy_type: EnemyType,
+    wave_number: u32,
+    difficulty: Difficulty,
+) -> u64 {
     let base_reward = match enemy_type {
         EnemyType::BasicScout => 10,
         EnemyType::FastRunner => 15,
         EnemyType::HeavySoldier => 25,
         EnemyType::Tank => 50,
         EnemyType::Boss => 200,
+        EnemyType::Healer => 20,
+        EnemyType::ShieldBearer => 30,
+        EnemyType::Splitter => 15,
+        EnemyType::Splitling => 3,
+        EnemyType::Brute => 40,
+        EnemyType::Flyer => 20,
     };
 
-    // Bonus gold for later waves (10% per wave)
-    let wave_multiplier = 1.0 + ((wave_number as f64 - 1.0) * 0.1);
-    ((base_reward as f64) * wave_multiplier) as u64
+    // Bonus gold for later waves (10% per wave), in tenths to stay in integer math
+    let wave_tenths = 9u64.saturating_add(wave_number as u64);
+    difficulty.scale_reward((base_reward as u64).saturating_mul(wave_tenths) / 10)
 }
 
 /// Calculate total cost of a tower including all upgrades
@@ -50,7 +61,7 @@ pub fn calculate_tower_total_cost(tower_type: TowerType, level: u8) -> u64 {
 /// Calculate sell value for a tower
 pub fn calculate_sell_value(tower_type: TowerType, level: u8, config: &EconomyConfig) -> u64 {
     let total_cost = calculate_tower_total_cost(tower_type, level);
-    ((total_cost as f64) * config.tower_sell_ratio) as u64
+    total_cost.saturating_mul(config.tower_sell_percent) / 100
 }
 
 /// Calculate wave completion bonus
@@ -72,20 +83,16 @@ pub fn calculate_total_tower_value(towers: &[(TowerType, u8)]) -> u64 {
         .sum()
 }
 
-/// Calculate efficiency score (damage per gold spent)
-pub fn calculate_tower_efficiency(tower_type: TowerType, level: u8) -> f64 {
+/// Calculate efficiency score (milli-damage per second per gold spent)
+pub fn calculate_tower_efficiency(tower_type: TowerType, level: u8) -> u64 {
     let cost = calculate_tower_total_cost(tower_type, level);
     let stats = tower_type.base_stats().upgraded(level);
 
     // Simple efficiency: damage per gold
-    // Factor in fire rate (damage per second)
-    let dps = (stats.damage as f64) / ((stats.fire_rate_ms as f64) / 1000.0);
+    // Factor in fire rate (damage per second, scaled by 1000)
+    let dps_milli = (stats.damage as u64).saturating_mul(1_000_000) / stats.fire_rate_ms.max(1);
 
-    if cost == 0 {
-        0.0
-    } else {
-        dps / (cost as f64)
-    }
+    dps_milli.checked_div(cost).unwrap_or(0)
 }
 
 /// Recommend tower purchase based on current gold and strategy
@@ -110,11 +117,7 @@ pub fn recommend_tower(current_gold: u64, strategy: Strategy) -> Option<TowerTyp
             // Return most efficient tower
             affordable_towers
                 .into_iter()
-                .max_by(|a, b| {
-                    let eff_a = calculate_tower_efficiency(*a, 1);
-                    let eff_b = calculate_tower_efficiency(*b, 1);
-                    eff_a.partial_cmp(&eff_b).unwrap_or(std::cmp::Ordering::Equal)
-                })
+                .max_by_key(|t| calculate_tower_efficiency(*t, 1))
         }
         Strategy::MaxDamage => {
             // Return highest damage tower
@@ -143,13 +146,19 @@ mod tests {
     #[test]
     fn test_calculate_kill_reward() {
         // Wave 1 rewards
-        assert_eq!(calculate_kill_reward(EnemyType::BasicScout, 1), 10);
-        assert_eq!(calculate_kill_reward(EnemyType::Boss, 1), 200);
+        let normal = Difficulty::Normal;
+        assert_eq!(calculate_kill_reward(EnemyType::BasicScout, 1, normal), 10);
+        assert_eq!(calculate_kill_reward(EnemyType::Boss, 1, normal), 200);
 
         // Wave 5 should give more gold (40% more)
-        let wave1_reward = calculate_kill_reward(EnemyType::BasicScout, 1);
-        let wave5_reward = calculate_kill_reward(EnemyType::BasicScout, 5);
+        let wave1_reward = calculate_kill_reward(EnemyType::BasicScout, 1, normal);
+        let wave5_reward = calculate_kill_reward(EnemyType::BasicScout, 5, normal);
         assert!(wave5_reward > wave1_reward);
+
+        // Harder presets pay less per kill
+        let boss = |difficulty| calculate_kill_reward(EnemyType::Boss, 1, difficulty);
+        assert_eq!(boss(Difficulty::Easy), 250);
+        assert_eq!(boss(Difficulty::Hard), 170);
     }
 
     #[test]
@@ -212,15 +221,15 @@ mod tests {
     fn test_calculate_tower_efficiency() {
         // Arrow tower: 10 damage, 500ms fire rate, 100 cost
         // DPS = 10 / 0.5 = 20
-        // Efficiency = 20 / 100 = 0.2
+        // Efficiency = 20 / 100 = 0.2 (200 milli)
         let arrow_eff = calculate_tower_efficiency(TowerType::Arrow, 1);
-        assert!(arrow_eff > 0.0);
+        assert_eq!(arrow_eff, 200);
 
         // Cannon tower: 50 damage, 2000ms fire rate, 250 cost
         // DPS = 50 / 2.0 = 25
-        // Efficiency = 25 / 250 = 0.1
+        // Efficiency = 25 / 250 = 0.1 (100 milli)
         let cannon_eff = calculate_tower_efficiency(TowerType::Cannon, 1);
-        assert!(cannon_eff > 0.0);
+        assert_eq!(cannon_eff, 100);
 
         // Arrow should be more efficient than Cannon
         assert!(arrow_eff > cannon_eff);
@@ -274,6 +283,6 @@ mod tests {
 
         assert_eq!(config.starting_gold, 500);
         assert_eq!(config.wave_completion_bonus, 50);
-        assert_eq!(config.tower_sell_ratio, 0.7);
+        assert_eq!(config.tower_sell_percent, 70);
     }
 }
@@ -0,0 +1,180 @@
+use crate::enemy::{Enemy, EnemyAbility};
+use crate::fixed::{apply_permille, PERMILLE};
+use crate::wave::SpawnGroup;
+use serde::{Deserialize, Serialize};
+
+/// Last wave of a normal game; endless escalation begins after it
+pub const ENDLESS_START_WAVE: u32 = 100;
+
+/// Waves between mutator unlocks
+pub const MUTATOR_INTERVAL: u32 = 10;
+
+const HEALTH_PERCENT_PER_WAVE: u32 = 5;
+const COUNT_PERCENT_PER_WAVE: u32 = 2;
+const MAX_EXTRA_COUNT_PERCENT: u32 = 300;
+const SPEED_PERMILLE_PER_WAVE: u32 = 5;
+const MAX_EXTRA_SPEED_PERMILLE: u32 = 1000;
+
+/// Extra armor from `Armored`, shared with the game mutator of the same name
+pub const ARMORED_BONUS: u32 = 10;
+/// Shield from `Shielded`, per-mille of max health
+pub const SHIELDED_PERMILLE: u32 = 200;
+/// Health restored each second by `Regenerating`, per-mille of max health
+pub const REGENERATING_PERMILLE: u32 = 10;
+/// Extra dodge chance from `Evasive`, per-mille
+pub const EVASIVE_BONUS_PERMILLE: u32 = 50;
+
+/// Lasting enemy upgrades unlocked deep into an endless game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndlessMutator {
+    /// +10 armor (wave 110)
+    Armored,
+    /// Spawn with a shield worth 20% of max health (wave 120)
+    Shielded,
+    /// Enemies without an ability regenerate 1% of max health per second (wave 130)
+    Regenerating,
+    /// +5% dodge chance (wave 140)
+    Evasive,
+}
+
+impl EndlessMutator {
+    /// In unlock order
+    pub const ALL: [EndlessMutator; 4] = [
+        Self::Armored,
+        Self::Shielded,
+        Self::Regenerating,
+        Self::Evasive,
+    ];
+
+    /// First wave this mutator applies to
+    pub fn unlock_wave(&self) -> u32 {
+        let index = Self::ALL.iter().position(|m| m == self).unwrap_or(0) as u32;
+        ENDLESS_START_WAVE + (index + 1) * MUTATOR_INTERVAL
+    }
+
+    pub fn apply(&self, enemy: &mut Enemy) {
+        match self {
+            Self::Armored => enemy.armor = enemy.armor.saturating_add(ARMORED_BONUS),
+            Self::Shielded => {
+                let shield = apply_permille(enemy.max_health, SHIELDED_PERMILLE);
+                enemy.shield = enemy.shield.saturating_add(shield);
+            }
+            Self::Regenerating => {
+                if enemy.ability.is_none() {
+                    enemy.ability = Some(EnemyAbility::Regenerate {
+                        per_second: apply_permille(enemy.max_health, REGENERATING_PERMILLE).max(1),
+                    });
+                }
+            }
+            Self::Evasive => {
+                enemy.dodge_chance_permille = enemy
+                    .dodge_chance_permille
+                    .saturating_add(EVASIVE_BONUS_PERMILLE)
+                    .min(PERMILLE);
+            }
+        }
+    }
+}
+
+/// How much harder one endless wave is than a normal wave of the same number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndlessScaling {
+    /// Health multiplier in percent (100 = unchanged)
+    pub health_percent: u32,
+    /// Enemy count multiplier in percent
+    pub count_percent: u32,
+    /// Speed multiplier in per-mille
+    pub speed_permille: u32,
+    pub mutators: Vec<EndlessMutator>,
+}
+
+impl EndlessScaling {
+    /// Scaling for an endless wave (no change up to `ENDLESS_START_WAVE`)
+    pub fn for_wave(wave_number: u32) -> Self {
+        let past = wave_number.saturating_sub(ENDLESS_START_WAVE);
+        Self {
+            health_percent: 100u32.saturating_add(past.saturating_mul(HEALTH_PERCENT_PER_WAVE)),
+            count_percent: 100
+                + past
+                    .saturating_mul(COUNT_PERCENT_PER_WAVE)
+                    .min(MAX_EXTRA_COUNT_PERCENT),
+            speed_permille: PERMILLE
+                + past
+                    .saturating_mul(SPEED_PERMILLE_PER_WAVE)
+                    .min(MAX_EXTRA_SPEED_PERMILLE),
+            mutators: EndlessMutator::ALL
+                .into_iter()
+                .filter(|mutator| wave_number >= mutator.unlock_wave())
+                .collect(),
+        }
+    }
+
+    /// Grow a spawn group's size and health
+    pub fn scale_group(&self, group: &mut SpawnGroup) {
+        let count = (group.count as u64) * (self.count_percent as u64) / 100;
+        group.count = count.clamp(1, u32::MAX as u64) as u32;
+        let health = (group.health_percent as u64) * (self.health_percent as u64) / 100;
+        group.health_percent = health.min(u32::MAX as u64) as u32;
+    }
+
+    /// Speed up a freshly spawned enemy and give it the unlocked mutators
+    pub fn apply(&self, enemy: &mut Enemy) {
+        enemy.speed = apply_permille(enemy.speed, self.speed_permille);
+        for mutator in &self.mutators {
+            mutator.apply(enemy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::EnemyType;
+
+    #[test]
+    fn test_endless_curve() {
+        let normal = EndlessScaling::for_wave(ENDLESS_START_WAVE);
+        assert_eq!(normal.health_percent, 100);
+        assert_eq!(normal.count_percent, 100);
+        assert_eq!(normal.speed_permille, 1000);
+        assert!(normal.mutators.is_empty());
+
+        let deep = EndlessScaling::for_wave(ENDLESS_START_WAVE + 20);
+        assert_eq!(deep.health_percent, 200);
+        assert_eq!(deep.count_percent, 140);
+        assert_eq!(deep.speed_permille, 1100);
+        assert_eq!(
+            deep.mutators,
+            vec![EndlessMutator::Armored, EndlessMutator::Shielded]
+        );
+
+        // Count and speed level off; health keeps climbing
+        let far = EndlessScaling::for_wave(1000);
+        assert_eq!(far.count_percent, 400);
+        assert_eq!(far.speed_permille, 2000);
+        assert_eq!(far.health_percent, 4600);
+        assert_eq!(far.mutators.len(), EndlessMutator::ALL.len());
+    }
+
+    #[test]
+    fn test_endless_mutators() {
+        let scaling = EndlessScaling::for_wave(EndlessMutator::Evasive.unlock_wave());
+        let mut group = SpawnGroup::new(EnemyType::BasicScout, 10, 0);
+        scaling.scale_group(&mut group);
+        assert_eq!(group.count, 18);
+        assert_eq!(group.health_percent, 300);
+
+        let mut enemy = Enemy::new(0, EnemyType::BasicScout, 140, (0, 0));
+        scaling.apply(&mut enemy);
+        assert_eq!(enemy.speed, 1200);
+        assert_eq!(enemy.armor, ARMORED_BONUS);
+        assert_eq!(enemy.shield, enemy.max_health / 5);
+        assert_eq!(
+            enemy.ability,
+            Some(EnemyAbility::Regenerate {
+                per_second: enemy.max_health / 100
+            })
+        );
+        assert_eq!(enemy.dodge_chance_permille, 50);
+    }
+}
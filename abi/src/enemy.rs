@@ -1,3 +1,7 @@
+use crate::difficulty::Difficulty;
+use crate::fixed::{apply_permille, tile_pos_to_milli, PERMILLE};
+use crate::status::{add_status_effect, StatusEffect, StatusKind};
+use crate::tower::DamageType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -7,43 +11,328 @@ pub enum EnemyType {
     FastRunner,
     Tank,
     Boss,
+    /// Periodically heals nearby enemies
+    Healer,
+    /// Carries a shield that absorbs damage before health
+    ShieldBearer,
+    /// Splits into Splitlings when killed
+    Splitter,
+    /// Small, fast child of a Splitter
+    Splitling,
+    /// Tough enemy that regenerates health
+    Brute,
+    /// Flies straight over the map, ignoring the ground path
+    Flyer,
 }
 
+/// How an enemy crosses the map, and which towers can hit it
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MovementClass {
+    /// Walks the grid path
+    #[default]
+    Ground,
+    /// Flies the grid's air route
+    Air,
+}
+
+/// Special behavior an enemy type brings, resolved every simulation step
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EnemyAbility {
+    /// Every `interval_micros`, restore `amount` health to other enemies within `radius` milli-tiles
+    Heal {
+        amount: u32,
+        radius: u32,
+        interval_micros: u64,
+    },
+    /// Starts with `capacity` shield that absorbs damage before health
+    Shield { capacity: u32 },
+    /// On death, spawn `count` enemies of type `child` where it died
+    Split { child: EnemyType, count: u8 },
+    /// Every second, restore `per_second` of its own health
+    Regenerate { per_second: u32 },
+}
+
+/// Cadence of regeneration ticks
+pub const REGENERATE_INTERVAL_MICROS: u64 = 1_000_000;
+
+/// What a boss does when it enters a new phase
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PhaseEffect {
+    /// Multiply movement speed by `speed_permille`
+    ChangeSpeed { speed_permille: u32 },
+    /// Summon `count` enemies of type `minion` where the boss stands
+    SpawnMinions { minion: EnemyType, count: u8 },
+    /// Ignore all damage for `duration_micros`
+    Immunity { duration_micros: u64 },
+    /// Towers within `radius` milli-tiles stop firing for `duration_micros`
+    DisableTowers { radius: u32, duration_micros: u64 },
+}
+
+/// A boss phase, entered once health drops to `health_threshold_permille` of max
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BossPhase {
+    pub health_threshold_permille: u32,
+    pub effects: &'static [PhaseEffect],
+}
+
+const BOSS_PHASES: &[BossPhase] = &[
+    BossPhase {
+        health_threshold_permille: 750,
+        effects: &[PhaseEffect::SpawnMinions {
+            minion: EnemyType::FastRunner,
+            count: 3,
+        }],
+    },
+    BossPhase {
+        health_threshold_permille: 500,
+        effects: &[
+            PhaseEffect::Immunity {
+                duration_micros: 2_000_000,
+            },
+            PhaseEffect::DisableTowers {
+                radius: 3000,
+                duration_micros: 3_000_000,
+            },
+        ],
+    },
+    BossPhase {
+        health_threshold_permille: 250,
+        effects: &[
+            PhaseEffect::ChangeSpeed {
+                speed_permille: 1500,
+            },
+            PhaseEffect::SpawnMinions {
+                minion: EnemyType::HeavySoldier,
+                count: 2,
+            },
+        ],
+    },
+];
+
 impl EnemyType {
+    /// Stats on Normal difficulty
     pub fn base_stats(&self, wave: u32) -> EnemyStats {
-        let health_mult = 1.0 + ((wave as f32) * 0.1);
+        self.stats(wave, Difficulty::Normal)
+    }
 
-        match self {
+    pub fn stats(&self, wave: u32, difficulty: Difficulty) -> EnemyStats {
+        // Health grows by the difficulty's percent per wave (+10% on Normal)
+        let health_percent =
+            100u32.saturating_add(wave.saturating_mul(difficulty.health_growth_percent()));
+        let scaled = |base: u32| base.saturating_mul(health_percent) / 100;
+        // Bosses grow at half that rate (+5% per wave on Normal), in half-percents
+        let boss_half_percent =
+            200u32.saturating_add(wave.saturating_mul(difficulty.health_growth_percent()));
+
+        let mut stats = match self {
             Self::BasicScout => EnemyStats {
-                health: (50.0 * health_mult) as u32,
-                speed: 1.0,
+                health: scaled(50),
+                speed: 1000,
                 gold_reward: 10,
                 damage_to_base: 1,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
             },
             Self::HeavySoldier => EnemyStats {
-                health: (150.0 * health_mult) as u32,
-                speed: 0.7,
+                health: scaled(150),
+                speed: 700,
                 gold_reward: 25,
                 damage_to_base: 2,
+                armor: 2,
+                resistances: Resistances {
+                    physical: 800,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
             },
             Self::FastRunner => EnemyStats {
-                health: (40.0 * health_mult) as u32,
-                speed: 1.8,
+                health: scaled(40),
+                speed: 1800,
                 gold_reward: 15,
                 damage_to_base: 1,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1250,
+                    lightning: 1000,
+                    explosive: 700,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 150,
             },
             Self::Tank => EnemyStats {
-                health: (500.0 * health_mult) as u32,
-                speed: 0.5,
+                health: scaled(500),
+                speed: 500,
                 gold_reward: 50,
                 damage_to_base: 5,
+                armor: 5,
+                resistances: Resistances {
+                    physical: 700,
+                    magic: 2000,
+                    ice: 800,
+                    lightning: 1000,
+                    explosive: 1200,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
             },
             Self::Boss => EnemyStats {
-                health: 2000_u32.saturating_add(wave.saturating_mul(100)),
-                speed: 0.8,
+                health: 2000u32.saturating_mul(boss_half_percent) / 200,
+                speed: 800,
                 gold_reward: 200,
                 damage_to_base: 10,
+                armor: 3,
+                resistances: Resistances {
+                    physical: 900,
+                    magic: 900,
+                    ice: 500,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::Healer => EnemyStats {
+                health: scaled(80),
+                speed: 900,
+                gold_reward: 20,
+                damage_to_base: 1,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1200,
+                    ice: 1000,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: Some(EnemyAbility::Heal {
+                    amount: scaled(10),
+                    radius: 2000,
+                    interval_micros: 2_000_000,
+                }),
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::ShieldBearer => EnemyStats {
+                health: scaled(120),
+                speed: 700,
+                gold_reward: 30,
+                damage_to_base: 2,
+                armor: 1,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1200,
+                    explosive: 800,
+                },
+                ability: Some(EnemyAbility::Shield {
+                    capacity: scaled(100),
+                }),
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::Splitter => EnemyStats {
+                health: scaled(100),
+                speed: 900,
+                gold_reward: 15,
+                damage_to_base: 2,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1000,
+                    explosive: 1200,
+                },
+                ability: Some(EnemyAbility::Split {
+                    child: EnemyType::Splitling,
+                    count: 3,
+                }),
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::Splitling => EnemyStats {
+                health: scaled(25),
+                speed: 1400,
+                gold_reward: 3,
+                damage_to_base: 1,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: None,
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::Brute => EnemyStats {
+                health: scaled(250),
+                speed: 600,
+                gold_reward: 40,
+                damage_to_base: 3,
+                armor: 3,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 900,
+                    ice: 1200,
+                    lightning: 1000,
+                    explosive: 1000,
+                },
+                ability: Some(EnemyAbility::Regenerate {
+                    per_second: scaled(8),
+                }),
+                movement: MovementClass::Ground,
+                dodge_chance_permille: 0,
+            },
+            Self::Flyer => EnemyStats {
+                health: scaled(60),
+                speed: 1200,
+                gold_reward: 20,
+                damage_to_base: 1,
+                armor: 0,
+                resistances: Resistances {
+                    physical: 1000,
+                    magic: 1000,
+                    ice: 1000,
+                    lightning: 1200,
+                    explosive: 500,
+                },
+                ability: None,
+                movement: MovementClass::Air,
+                dodge_chance_permille: 0,
             },
+        };
+        stats.gold_reward = difficulty.scale_reward(stats.gold_reward);
+        stats
+    }
+
+    /// Phases a boss goes through as it loses health, highest threshold first
+    pub fn boss_phases(&self) -> &'static [BossPhase] {
+        match self {
+            Self::Boss => BOSS_PHASES,
+            _ => &[],
         }
     }
 }
@@ -51,68 +340,293 @@ impl EnemyType {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct EnemyStats {
     pub health: u32,
-    pub speed: f32,
+    /// Movement speed in milli-tiles per second
+    pub speed: u32,
     pub gold_reward: u64,
     pub damage_to_base: u32,
+    /// Flat reduction applied to physical and explosive hits
+    pub armor: u32,
+    pub resistances: Resistances,
+    pub ability: Option<EnemyAbility>,
+    pub movement: MovementClass,
+    /// Chance in 1000 that a tower hit misses entirely
+    pub dodge_chance_permille: u32,
+}
+
+/// Damage taken per damage type, in per-mille (1000 = normal, 2000 = double)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Resistances {
+    pub physical: u32,
+    pub magic: u32,
+    pub ice: u32,
+    pub lightning: u32,
+    pub explosive: u32,
+}
+
+impl Resistances {
+    pub fn multiplier(&self, damage_type: DamageType) -> u32 {
+        match damage_type {
+            DamageType::Physical => self.physical,
+            DamageType::Magic => self.magic,
+            DamageType::Ice => self.ice,
+            DamageType::Lightning => self.lightning,
+            DamageType::Explosive => self.explosive,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enemy {
     pub id: u64,
     pub enemy_type: EnemyType,
-    pub position: (f32, f32),
+    /// Position in milli-tiles
+    pub position: (i32, i32),
     pub path_index: usize,
+    /// Map lane the enemy follows
+    pub lane: u8,
     pub health: u32,
     pub max_health: u32,
-    pub speed: f32,
+    /// Movement speed in milli-tiles per second
+    pub speed: u32,
     pub gold_reward: u64,
     pub damage_to_base: u32,
-    pub slow_multiplier: f32,
+    pub armor: u32,
+    pub resistances: Resistances,
+    pub ability: Option<EnemyAbility>,
+    pub movement: MovementClass,
+    /// Chance in 1000 that a tower hit misses entirely
+    pub dodge_chance_permille: u32,
+    /// Remaining shield, absorbed before health
+    pub shield: u32,
+    /// Time accumulated towards the next heal or regeneration tick
+    pub ability_timer_micros: u64,
+    /// Active status effects (slow, burn, poison, stun, armor shred, immunity)
+    pub status_effects: Vec<StatusEffect>,
+    /// Number of boss phases entered so far
+    pub boss_phase: u8,
+    /// Difficulty the enemy's stats were rolled for; enemies it spawns inherit it
+    pub difficulty: Difficulty,
 }
 
 impl Enemy {
+    /// Enemy on Normal difficulty
     pub fn new(id: u64, enemy_type: EnemyType, wave: u32, spawn_pos: (u8, u8)) -> Self {
-        let stats = enemy_type.base_stats(wave);
+        Self::with_difficulty(id, enemy_type, wave, spawn_pos, Difficulty::Normal)
+    }
+
+    pub fn with_difficulty(
+        id: u64,
+        enemy_type: EnemyType,
+        wave: u32,
+        spawn_pos: (u8, u8),
+        difficulty: Difficulty,
+    ) -> Self {
+        let stats = enemy_type.stats(wave, difficulty);
         Self {
             id,
             enemy_type,
-            position: (spawn_pos.0 as f32, spawn_pos.1 as f32),
+            position: tile_pos_to_milli(spawn_pos),
             path_index: 0,
+            lane: 0,
             health: stats.health,
             max_health: stats.health,
             speed: stats.speed,
             gold_reward: stats.gold_reward,
             damage_to_base: stats.damage_to_base,
-            slow_multiplier: 1.0,
+            armor: stats.armor,
+            resistances: stats.resistances,
+            ability: stats.ability,
+            movement: stats.movement,
+            dodge_chance_permille: stats.dodge_chance_permille,
+            shield: match stats.ability {
+                Some(EnemyAbility::Shield { capacity }) => capacity,
+                _ => 0,
+            },
+            ability_timer_micros: 0,
+            status_effects: Vec::new(),
+            boss_phase: 0,
+            difficulty,
         }
     }
 
+    /// Scale health by a percentage (e.g. from a wave script)
+    pub fn scale_health(&mut self, percent: u32) {
+        let scaled = (self.max_health as u64) * (percent as u64) / 100;
+        self.max_health = scaled.clamp(1, u32::MAX as u64) as u32;
+        self.health = self.max_health;
+    }
+
     pub fn is_alive(&self) -> bool {
         self.health > 0
     }
 
-    pub fn effective_speed(&self) -> f32 {
-        self.speed * self.slow_multiplier
+    /// Current speed in milli-tiles per second
+    pub fn effective_speed(&self) -> u32 {
+        if self.is_stunned() {
+            return 0;
+        }
+        apply_permille(self.speed, self.slow_multiplier())
     }
 
+    /// Apply damage to the shield first, then health; returns true if killed
     pub fn take_damage(&mut self, damage: u32) -> bool {
-        self.health = self.health.saturating_sub(damage);
+        if self.is_immune() {
+            return false;
+        }
+        let absorbed = damage.min(self.shield);
+        self.shield -= absorbed;
+        self.health = self.health.saturating_sub(damage - absorbed);
         !self.is_alive()
     }
 
-    pub fn apply_slow(&mut self, multiplier: f32) {
-        self.slow_multiplier = multiplier.min(self.slow_multiplier);
+    /// Restore health up to max, returning the amount actually healed
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let healed = amount.min(self.max_health.saturating_sub(self.health));
+        self.health += healed;
+        healed
     }
 
-    pub fn reset_slow(&mut self) {
-        self.slow_multiplier = 1.0;
+    /// Advance the ability timer, returning how many ability ticks fired
+    pub fn advance_ability(&mut self, delta_micros: u64) -> u64 {
+        let interval = match self.ability {
+            Some(EnemyAbility::Heal {
+                interval_micros, ..
+            }) => interval_micros,
+            Some(EnemyAbility::Regenerate { .. }) => REGENERATE_INTERVAL_MICROS,
+            _ => return 0,
+        };
+        if interval == 0 {
+            return 0;
+        }
+
+        self.ability_timer_micros = self.ability_timer_micros.saturating_add(delta_micros);
+        let ticks = self.ability_timer_micros / interval;
+        self.ability_timer_micros %= interval;
+        ticks
+    }
+
+    /// Children a splitter leaves behind, placed where it died
+    pub fn split_children(&self, first_id: u64, wave: u32) -> Vec<Enemy> {
+        match self.ability {
+            Some(EnemyAbility::Split { child, count }) => {
+                self.spawn_here(first_id, child, count, wave)
+            }
+            _ => Vec::new(),
+        }
     }
 
-    pub fn health_percentage(&self) -> f32 {
+    /// `count` new enemies of type `enemy_type`, placed where this one stands
+    pub fn spawn_here(
+        &self,
+        first_id: u64,
+        enemy_type: EnemyType,
+        count: u8,
+        wave: u32,
+    ) -> Vec<Enemy> {
+        (0..count as u64)
+            .map(|offset| {
+                let mut enemy = Enemy::with_difficulty(
+                    first_id + offset,
+                    enemy_type,
+                    wave,
+                    (0, 0),
+                    self.difficulty,
+                );
+                enemy.position = self.position;
+                enemy.path_index = self.path_index;
+                enemy.lane = self.lane;
+                enemy
+            })
+            .collect()
+    }
+
+    /// Enter the next boss phase if health has dropped to its threshold
+    ///
+    /// Returns the new phase number (1-based) and its definition. Call
+    /// repeatedly to catch up when one hit crosses several thresholds.
+    pub fn advance_boss_phase(&mut self) -> Option<(u8, &'static BossPhase)> {
+        let phase = self
+            .enemy_type
+            .boss_phases()
+            .get(self.boss_phase as usize)?;
+        let health_permille =
+            (self.health as u64) * (PERMILLE as u64) / (self.max_health.max(1) as u64);
+        if health_permille > phase.health_threshold_permille as u64 {
+            return None;
+        }
+        self.boss_phase = self.boss_phase.saturating_add(1);
+        Some((self.boss_phase, phase))
+    }
+
+    /// Apply a status effect, following its stacking rule
+    pub fn apply_status(&mut self, effect: StatusEffect) {
+        add_status_effect(&mut self.status_effects, effect);
+    }
+
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.status_effects.iter().any(|e| e.kind == kind)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.has_status(StatusKind::Stun)
+    }
+
+    pub fn is_immune(&self) -> bool {
+        self.has_status(StatusKind::Immune)
+    }
+
+    /// Speed multiplier in per-mille from the active slow (1000 = full speed)
+    pub fn slow_multiplier(&self) -> u32 {
+        let slow = self
+            .status_effects
+            .iter()
+            .filter(|e| e.kind == StatusKind::Slow)
+            .map(|e| e.magnitude)
+            .max()
+            .unwrap_or(0);
+        PERMILLE.saturating_sub(slow)
+    }
+
+    /// Damage taken multiplier in per-mille, raised by armor shred stacks
+    pub fn damage_taken_permille(&self) -> u32 {
+        self.status_effects
+            .iter()
+            .filter(|e| e.kind == StatusKind::ArmorShred)
+            .fold(PERMILLE, |total, e| total.saturating_add(e.magnitude))
+    }
+
+    /// Advance all status effects, returning DoT damage per source tower
+    ///
+    /// Damage is not applied here so the caller can attribute kills.
+    pub fn tick_status_effects(&mut self, delta_micros: u64) -> Vec<(u64, u32)> {
+        let mut damage: Vec<(u64, u32)> = Vec::new();
+        for effect in self.status_effects.iter_mut() {
+            let dealt = effect.advance(delta_micros);
+            if dealt == 0 {
+                continue;
+            }
+            match damage
+                .iter_mut()
+                .find(|(tower_id, _)| *tower_id == effect.source_tower_id)
+            {
+                Some((_, total)) => *total = total.saturating_add(dealt),
+                None => damage.push((effect.source_tower_id, dealt)),
+            }
+        }
+        // Damage-over-time keeps ticking down but deals nothing while immune
+        if self.is_immune() {
+            damage.clear();
+        }
+        self.status_effects.retain(|e| !e.is_expired());
+        damage
+    }
+
+    /// Remaining health as a whole percentage (0-100)
+    pub fn health_percentage(&self) -> u32 {
         if self.max_health == 0 {
-            0.0
+            0
         } else {
-            (self.health as f32) / (self.max_health as f32)
+            ((self.health as u64) * 100 / (self.max_health as u64)) as u32
         }
     }
 }
@@ -125,7 +639,7 @@ mod tests {
     fn test_enemy_type_stats() {
         let stats = EnemyType::BasicScout.base_stats(1);
         assert_eq!(stats.health, 55); // 50 * 1.1
-        assert_eq!(stats.speed, 1.0);
+        assert_eq!(stats.speed, 1000);
         assert_eq!(stats.gold_reward, 10);
         assert_eq!(stats.damage_to_base, 1);
     }
@@ -148,15 +662,137 @@ mod tests {
         assert_eq!(wave10_boss.health, 3000); // 2000 + 1000
     }
 
+    #[test]
+    fn test_boss_health_follows_difficulty() {
+        let healths: Vec<u32> = Difficulty::ALL
+            .iter()
+            .map(|difficulty| EnemyType::Boss.stats(10, *difficulty).health)
+            .collect();
+        assert_eq!(healths, vec![2700, 3000, 3400, 4000]);
+        assert!(Difficulty::ALL
+            .iter()
+            .all(|difficulty| EnemyType::Boss.stats(0, *difficulty).health == 2000));
+    }
+
+    #[test]
+    fn test_resistance_matrix() {
+        let tank = EnemyType::Tank.base_stats(1);
+        assert_eq!(tank.resistances.multiplier(DamageType::Magic), 2000);
+        assert_eq!(tank.resistances.multiplier(DamageType::Physical), 700);
+
+        let runner = EnemyType::FastRunner.base_stats(1);
+        assert!(runner.resistances.multiplier(DamageType::Ice) > 1000);
+        assert!(runner.resistances.multiplier(DamageType::Explosive) < 1000);
+
+        // Scouts are the neutral baseline
+        let scout = EnemyType::BasicScout.base_stats(1);
+        assert_eq!(scout.armor, 0);
+        assert!(DamageType::ALL
+            .iter()
+            .all(|t| scout.resistances.multiplier(*t) == 1000));
+    }
+
+    #[test]
+    fn test_shield_absorbs_damage_first() {
+        let mut enemy = Enemy::new(0, EnemyType::ShieldBearer, 0, (0, 0));
+        assert_eq!(enemy.shield, 100);
+        assert_eq!(enemy.health, 120);
+
+        assert!(!enemy.take_damage(70));
+        assert_eq!((enemy.shield, enemy.health), (30, 120));
+
+        assert!(!enemy.take_damage(50));
+        assert_eq!((enemy.shield, enemy.health), (0, 100));
+    }
+
+    #[test]
+    fn test_regeneration_and_heal_cap() {
+        let mut brute = Enemy::new(0, EnemyType::Brute, 0, (0, 0));
+        brute.take_damage(50);
+
+        assert_eq!(brute.advance_ability(600_000), 0);
+        assert_eq!(brute.advance_ability(1_500_000), 2); // 2.1s elapsed
+        assert_eq!(brute.heal(1000), 50); // Capped at max health
+        assert_eq!(brute.health, brute.max_health);
+
+        // Abilities without a cadence never tick
+        let mut scout = Enemy::new(1, EnemyType::BasicScout, 0, (0, 0));
+        assert_eq!(scout.advance_ability(10_000_000), 0);
+    }
+
+    #[test]
+    fn test_split_children() {
+        let mut splitter = Enemy::new(0, EnemyType::Splitter, 1, (0, 0));
+        splitter.position = (4500, 2000);
+        splitter.path_index = 3;
+
+        let children = splitter.split_children(10, 1);
+        let ids: Vec<u64> = children.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![10, 11, 12]);
+        assert!(children.iter().all(|e| e.enemy_type == EnemyType::Splitling
+            && e.position == (4500, 2000)
+            && e.path_index == 3));
+
+        assert!(Enemy::new(1, EnemyType::Tank, 1, (0, 0))
+            .split_children(10, 1)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_boss_phases_follow_health() {
+        let mut boss = Enemy::new(0, EnemyType::Boss, 0, (0, 0));
+        assert_eq!(boss.advance_boss_phase(), None);
+
+        // One big hit crosses the first two thresholds
+        boss.take_damage(1100);
+        assert_eq!(boss.advance_boss_phase().map(|(n, _)| n), Some(1));
+        let (phase, definition) = boss.advance_boss_phase().unwrap();
+        assert_eq!(phase, 2);
+        assert_eq!(definition.health_threshold_permille, 500);
+        assert_eq!(boss.advance_boss_phase(), None);
+
+        // Other enemies have no phases
+        let mut tank = Enemy::new(1, EnemyType::Tank, 0, (0, 0));
+        tank.take_damage(tank.health - 1);
+        assert_eq!(tank.advance_boss_phase(), None);
+    }
+
+    #[test]
+    fn test_immunity_blocks_damage() {
+        let mut boss = Enemy::new(0, EnemyType::Boss, 0, (0, 0));
+        boss.apply_status(StatusEffect::new(StatusKind::Immune, 0, 1_000_000, 0));
+        boss.apply_status(StatusEffect::new(StatusKind::Burn, 10, 2_000_000, 1));
+
+        assert!(!boss.take_damage(u32::MAX));
+        assert_eq!(boss.health, boss.max_health);
+        assert!(boss.tick_status_effects(1_000_000).is_empty());
+
+        // Burn resumes once immunity wears off
+        assert_eq!(boss.tick_status_effects(500_000), vec![(1, 10)]);
+    }
+
+    #[test]
+    fn test_movement_classes() {
+        assert_eq!(
+            Enemy::new(0, EnemyType::Flyer, 1, (0, 0)).movement,
+            MovementClass::Air
+        );
+        assert_eq!(
+            Enemy::new(1, EnemyType::Boss, 1, (0, 0)).movement,
+            MovementClass::Ground
+        );
+    }
+
     #[test]
     fn test_enemy_creation() {
         let enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
         assert_eq!(enemy.id, 0);
         assert_eq!(enemy.enemy_type, EnemyType::BasicScout);
-        assert_eq!(enemy.position, (0.0, 0.0));
+        assert_eq!(enemy.position, (0, 0));
         assert_eq!(enemy.path_index, 0);
         assert!(enemy.is_alive());
-        assert_eq!(enemy.slow_multiplier, 1.0);
+        assert_eq!(enemy.slow_multiplier(), 1000);
+        assert!(enemy.status_effects.is_empty());
     }
 
     #[test]
@@ -180,34 +816,65 @@ mod tests {
     #[test]
     fn test_enemy_slow() {
         let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
-        assert_eq!(enemy.effective_speed(), 1.0);
+        assert_eq!(enemy.effective_speed(), 1000);
 
-        // Apply slow (30% reduction)
-        enemy.apply_slow(0.7);
-        assert_eq!(enemy.slow_multiplier, 0.7);
-        assert_eq!(enemy.effective_speed(), 0.7);
+        // Apply slow (30% reduction) for one second
+        enemy.apply_status(StatusEffect::new(StatusKind::Slow, 300, 1_000_000, 0));
+        assert_eq!(enemy.slow_multiplier(), 700);
+        assert_eq!(enemy.effective_speed(), 700);
 
         // Apply stronger slow (should keep the stronger one)
-        enemy.apply_slow(0.5);
-        assert_eq!(enemy.slow_multiplier, 0.5);
-        assert_eq!(enemy.effective_speed(), 0.5);
+        enemy.apply_status(StatusEffect::new(StatusKind::Slow, 500, 500_000, 0));
+        assert_eq!(enemy.slow_multiplier(), 500);
+        assert_eq!(enemy.effective_speed(), 500);
+
+        // Slow persists until its duration runs out
+        enemy.tick_status_effects(900_000);
+        assert_eq!(enemy.effective_speed(), 500);
+        enemy.tick_status_effects(100_000);
+        assert_eq!(enemy.slow_multiplier(), 1000);
+        assert_eq!(enemy.effective_speed(), 1000);
+    }
+
+    #[test]
+    fn test_enemy_stun_and_shred() {
+        let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
+        enemy.apply_status(StatusEffect::new(StatusKind::Stun, 0, 300_000, 0));
+        assert_eq!(enemy.effective_speed(), 0);
+
+        enemy.apply_status(StatusEffect::new(StatusKind::ArmorShred, 100, 2_000_000, 0));
+        enemy.apply_status(StatusEffect::new(StatusKind::ArmorShred, 100, 2_000_000, 0));
+        assert_eq!(enemy.damage_taken_permille(), 1200);
+
+        enemy.tick_status_effects(300_000);
+        assert_eq!(enemy.effective_speed(), 1000);
+        assert_eq!(enemy.damage_taken_permille(), 1200);
+    }
+
+    #[test]
+    fn test_enemy_dot_attribution() {
+        let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
+        enemy.apply_status(StatusEffect::new(StatusKind::Burn, 5, 2_000_000, 3));
+        enemy.apply_status(StatusEffect::new(StatusKind::Poison, 2, 2_000_000, 4));
+        enemy.apply_status(StatusEffect::new(StatusKind::Poison, 2, 2_000_000, 4));
+
+        let damage = enemy.tick_status_effects(1_000_000);
+        assert_eq!(damage, vec![(3, 10), (4, 8)]);
 
-        // Reset slow
-        enemy.reset_slow();
-        assert_eq!(enemy.slow_multiplier, 1.0);
-        assert_eq!(enemy.effective_speed(), 1.0);
+        // Damage is reported, not applied
+        assert_eq!(enemy.health, enemy.max_health);
     }
 
     #[test]
     fn test_enemy_health_percentage() {
         let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
-        assert_eq!(enemy.health_percentage(), 1.0);
+        assert_eq!(enemy.health_percentage(), 100);
 
         enemy.take_damage(enemy.max_health / 2);
-        assert!((enemy.health_percentage() - 0.5).abs() < 0.1);
+        assert_eq!(enemy.health_percentage(), 50);
 
         enemy.take_damage(enemy.max_health);
-        assert_eq!(enemy.health_percentage(), 0.0);
+        assert_eq!(enemy.health_percentage(), 0);
     }
 
     #[test]
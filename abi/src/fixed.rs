@@ -0,0 +1,109 @@
+/// Milli-tiles per grid tile
+pub const MILLI_PER_TILE: i32 = 1000;
+
+/// Per-mille value meaning "unchanged" (100%)
+pub const PERMILLE: u32 = 1000;
+
+/// Microseconds per second, for per-second rates
+pub const MICROS_PER_SECOND: u64 = 1_000_000;
+
+/// Convert a tile coordinate to milli-tiles
+pub fn tile_to_milli(tile: u8) -> i32 {
+    (tile as i32) * MILLI_PER_TILE
+}
+
+/// Convert a tile position to a milli-tile position
+pub fn tile_pos_to_milli(pos: (u8, u8)) -> (i32, i32) {
+    (tile_to_milli(pos.0), tile_to_milli(pos.1))
+}
+
+/// Convert a milli-tile position to the tile it lies on
+pub fn milli_pos_to_tile(pos: (i32, i32)) -> (u8, u8) {
+    let to_tile = |v: i32| (v.max(0) + MILLI_PER_TILE / 2) / MILLI_PER_TILE;
+    (
+        to_tile(pos.0).min(u8::MAX as i32) as u8,
+        to_tile(pos.1).min(u8::MAX as i32) as u8,
+    )
+}
+
+/// Convert a milli-unit (milli-tiles or per-mille) value to a float for display
+pub fn milli_to_f64(value: i64) -> f64 {
+    (value as f64) / 1000.0
+}
+
+/// Integer square root (floor)
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method starting from an over-estimate
+    let mut x = n / 2 + 1;
+    let mut y = (x + n / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Squared distance between two milli-tile positions
+pub fn distance_squared(a: (i32, i32), b: (i32, i32)) -> u64 {
+    let dx = (a.0 as i64 - b.0 as i64).unsigned_abs();
+    let dy = (a.1 as i64 - b.1 as i64).unsigned_abs();
+    dx * dx + dy * dy
+}
+
+/// Distance between two milli-tile positions, in milli-tiles
+pub fn distance(a: (i32, i32), b: (i32, i32)) -> u32 {
+    isqrt(distance_squared(a, b)).min(u32::MAX as u64) as u32
+}
+
+/// Check whether two milli-tile positions are within `radius` milli-tiles
+pub fn within_radius(a: (i32, i32), b: (i32, i32), radius: u32) -> bool {
+    let radius = radius as u64;
+    distance_squared(a, b) <= radius * radius
+}
+
+/// Scale a value by a per-mille multiplier
+pub fn apply_permille(value: u32, permille: u32) -> u32 {
+    ((value as u64) * (permille as u64) / (PERMILLE as u64)).min(u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(2_000_000), 1414);
+        assert_eq!(isqrt(u64::MAX), 4_294_967_295);
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance((0, 0), (3000, 4000)), 5000);
+        assert_eq!(distance((1000, 1000), (0, 0)), 1414);
+        assert!(within_radius((0, 0), (3000, 0), 3000));
+        assert!(!within_radius((0, 0), (3000, 1), 3000));
+    }
+
+    #[test]
+    fn test_tile_conversions() {
+        assert_eq!(tile_pos_to_milli((5, 10)), (5000, 10000));
+        assert_eq!(milli_pos_to_tile((5499, 10500)), (5, 11));
+        assert_eq!(milli_pos_to_tile((-200, 0)), (0, 0));
+        assert_eq!(milli_to_f64(1500), 1.5);
+    }
+
+    #[test]
+    fn test_apply_permille() {
+        assert_eq!(apply_permille(1000, 700), 700);
+        assert_eq!(apply_permille(55, 500), 27);
+        assert_eq!(apply_permille(u32::MAX, 2000), u32::MAX);
+    }
+}
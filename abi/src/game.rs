@@ -1,4 +1,16 @@
+use crate::difficulty::Difficulty;
+use crate::endless::EndlessScaling;
+use crate::enemy::MovementClass;
+use crate::fixed::{distance, tile_pos_to_milli};
+use crate::map::{Decoration, MapDefinition};
+use crate::mapgen::ProceduralMap;
+use crate::mutator::Mutator;
+use crate::pathfinding::{would_block_path, FlowField, Position, Route};
+use crate::terrain::{self, Terrain, TerrainMap};
+use crate::tower::TowerType;
+use crate::wave::{WaveConfig, WaveScript};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum GameStatus {
@@ -16,6 +28,93 @@ pub struct GameConfig {
     pub starting_gold: u64,
     pub base_health: u32,
     pub max_towers: usize,
+    /// Procedural wave settings (used when there is no wave script)
+    pub wave_config: WaveConfig,
+    /// Scripted campaign; replaces procedural waves when set
+    pub wave_script: Option<WaveScript>,
+    /// Tower shots travel as projectiles instead of hitting instantly
+    pub projectiles: bool,
+    /// Open field: towers may go anywhere that leaves a route, and enemies path around them
+    pub mazing: bool,
+    /// Registry map the game is played on (the classic map when unset)
+    pub map_id: Option<String>,
+    /// Seed and settings that regenerate this game's procedural map
+    pub procedural_map: Option<ProceduralMap>,
+    /// Waves never run out and escalate along the endless curve
+    pub endless: bool,
+    /// Preset behind enemy health growth, kill rewards, starting gold and lives
+    pub difficulty: Difficulty,
+    /// Rule changes active for the whole game
+    pub mutators: Vec<Mutator>,
+    /// Week of the weekly challenge this game plays, if any
+    pub challenge_week: Option<u64>,
+}
+
+impl GameConfig {
+    /// Last wave of the game, if the campaign has a fixed length
+    pub fn final_wave(&self) -> Option<u32> {
+        self.wave_script.as_ref().map(|script| script.wave_count())
+    }
+
+    /// The game's mutators plus any the script or the endless curve adds for
+    /// a wave (each applies once, however many sources list it)
+    pub fn mutators_for_wave(&self, wave_number: u32) -> Vec<Mutator> {
+        let mut mutators = self.mutators.clone();
+        let mut extra = Vec::new();
+        if let Some(script) = &self.wave_script {
+            extra.extend_from_slice(script.mutators(wave_number));
+        }
+        if self.endless {
+            extra.extend(
+                EndlessScaling::for_wave(wave_number)
+                    .mutators
+                    .into_iter()
+                    .map(Mutator::from),
+            );
+        }
+        for mutator in extra {
+            if !mutators.contains(&mutator) {
+                mutators.push(mutator);
+            }
+        }
+        mutators
+    }
+}
+
+/// Per-game rules the host picks when creating a game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameSettings {
+    /// Scripted campaign; replaces procedural waves when set
+    pub wave_script: Option<WaveScript>,
+    /// Tower shots travel as projectiles instead of hitting instantly
+    pub projectiles: bool,
+    /// Open-field mazing instead of the fixed path
+    pub mazing: bool,
+    /// Registry map to play on (the classic map when unset)
+    pub map_id: Option<String>,
+    /// Generate a fresh map instead of using the classic or a registry map
+    pub procedural_map: Option<ProceduralMap>,
+    pub difficulty: Difficulty,
+    /// Rule changes for the whole game (e.g. this week's challenge)
+    pub mutators: Vec<Mutator>,
+    /// Week of the weekly challenge, whose mutators then fill `mutators`
+    pub challenge_week: Option<u64>,
+}
+
+impl GameSettings {
+    /// Copy these settings onto a game's config
+    pub fn apply_to(self, config: &mut GameConfig) {
+        config.wave_script = self.wave_script;
+        config.projectiles = self.projectiles;
+        config.mazing = self.mazing;
+        config.map_id = self.map_id;
+        config.procedural_map = self.procedural_map;
+        config.difficulty = self.difficulty;
+        config.starting_gold = self.difficulty.starting_gold();
+        config.base_health = self.difficulty.base_health();
+        config.mutators = self.mutators;
+        config.challenge_week = self.challenge_week;
+    }
 }
 
 impl Default for GameConfig {
@@ -23,9 +122,19 @@ impl Default for GameConfig {
         Self {
             grid_width: 20,
             grid_height: 20,
-            starting_gold: 500,
-            base_health: 20,
+            starting_gold: Difficulty::Normal.starting_gold(),
+            base_health: Difficulty::Normal.base_health(),
             max_towers: 50,
+            wave_config: WaveConfig::default(),
+            wave_script: None,
+            projectiles: false,
+            mazing: false,
+            map_id: None,
+            procedural_map: None,
+            endless: false,
+            difficulty: Difficulty::Normal,
+            mutators: Vec::new(),
+            challenge_week: None,
         }
     }
 }
@@ -34,55 +143,209 @@ impl Default for GameConfig {
 pub struct Grid {
     pub width: u8,
     pub height: u8,
-    pub path: Vec<(u8, u8)>,
-    pub spawn_point: (u8, u8),
+    /// Routes from the spawn points to the base; waves pick one by index
+    pub lanes: Vec<Lane>,
     pub base_point: (u8, u8),
+    /// Distance map to the base that ground enemies follow (mazing only)
+    pub flow_field: Option<FlowField>,
+    /// Non-grass tiles: mud, roads and bridges for enemies, high ground, water,
+    /// rock and runes for towers; every other tile is grass
+    pub terrain: TerrainMap,
+    /// Tiles no tower may be built on
+    pub unbuildable: BTreeSet<(u8, u8)>,
+    /// When set, towers may only be built on these tiles
+    pub build_pads: Option<BTreeSet<(u8, u8)>>,
+    pub decorations: Vec<Decoration>,
 }
 
-impl Grid {
-    pub fn new() -> Self {
+/// One way from a spawn point to the base
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lane {
+    pub spawn_point: (u8, u8),
+    /// Ground path, tile by tile
+    pub path: Vec<(u8, u8)>,
+    /// Waypoints flying enemies follow; ignores the ground path
+    pub air_path: Vec<(u8, u8)>,
+    /// Route length from each ground waypoint to the base, in milli-tiles
+    pub path_to_base: Vec<u32>,
+    /// Route length from each air waypoint to the base, in milli-tiles
+    pub air_path_to_base: Vec<u32>,
+}
+
+impl Lane {
+    /// Lane along `path`, starting at its first tile
+    pub fn new(path: Vec<(u8, u8)>, base_point: (u8, u8)) -> Self {
+        let spawn_point = path.first().copied().unwrap_or(base_point);
+        let air_path = Grid::straight_air_path(spawn_point, base_point);
         Self {
-            width: 20,
-            height: 20,
-            spawn_point: (0, 10),
-            base_point: (19, 14),
-            path: Self::generate_default_path(),
+            spawn_point,
+            path_to_base: lengths_to_end(&path),
+            air_path_to_base: lengths_to_end(&air_path),
+            path,
+            air_path,
         }
     }
 
-    fn generate_default_path() -> Vec<(u8, u8)> {
-        let mut path = Vec::new();
+    /// Route length left to the base from waypoint `index` of the movement class's path
+    pub fn length_from(&self, movement: MovementClass, index: usize) -> u32 {
+        let lengths = match movement {
+            MovementClass::Ground => &self.path_to_base,
+            MovementClass::Air => &self.air_path_to_base,
+        };
+        lengths.get(index).copied().unwrap_or(0)
+    }
+}
 
-        // Horizontal segment from spawn
-        for x in 0..8 {
-            path.push((x, 10));
-        }
+/// Suffix sums of a path's legs: the distance from each waypoint to the last
+fn lengths_to_end(path: &[(u8, u8)]) -> Vec<u32> {
+    let mut lengths = vec![0u32; path.len()];
+    for index in (0..path.len().saturating_sub(1)).rev() {
+        let leg = distance(
+            tile_pos_to_milli(path[index]),
+            tile_pos_to_milli(path[index + 1]),
+        );
+        lengths[index] = lengths[index + 1].saturating_add(leg);
+    }
+    lengths
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        MapDefinition::classic().to_grid()
+    }
+
+    /// Air route flying straight from spawn to base
+    pub fn straight_air_path(spawn_point: (u8, u8), base_point: (u8, u8)) -> Vec<(u8, u8)> {
+        vec![spawn_point, base_point]
+    }
+
+    /// Lane by index; lanes past the last one fall back to lane 0
+    pub fn lane(&self, index: u8) -> &Lane {
+        self.lanes
+            .get(index as usize)
+            .or(self.lanes.first())
+            .expect("A grid always has a lane")
+    }
 
-        // Vertical segment going up
-        for y in (5..10).rev() {
-            path.push((7, y));
+    pub fn spawn_points(&self) -> Vec<(u8, u8)> {
+        self.lanes.iter().map(|lane| lane.spawn_point).collect()
+    }
+
+    /// How an enemy of the given movement class gets from a lane's spawn to the base
+    pub fn route(&self, movement: MovementClass, lane: u8) -> Route<'_> {
+        match (movement, &self.flow_field) {
+            (MovementClass::Ground, Some(field)) => Route::Flow(field),
+            (MovementClass::Ground, None) => Route::Waypoints(&self.lane(lane).path),
+            (MovementClass::Air, _) => Route::Waypoints(&self.lane(lane).air_path),
         }
+    }
+
+    /// Open grid for mazing: ground enemies follow a flow field around towers
+    pub fn open_field() -> Self {
+        let mut grid = Self::new();
+        grid.build_flow_field()
+            .expect("An empty grid always has a route");
+        grid
+    }
+
+    /// Route ground enemies over a flow field built from this grid's terrain
+    pub fn build_flow_field(&mut self) -> Result<(), &'static str> {
+        self.flow_field = Some(FlowField::build_on_terrain(
+            Position::from_tuple(self.base_point),
+            self.width,
+            self.height,
+            &HashSet::new(),
+            &self.terrain,
+        ));
+        self.refresh_paths()
+    }
+
+    pub fn terrain_at(&self, tile: (u8, u8)) -> Terrain {
+        terrain::terrain_at(&self.terrain, tile)
+    }
 
-        // Horizontal segment going right
-        for x in 7..15 {
-            path.push((x, 5));
+    /// Whether the map lets towers stand on this tile (paths aside)
+    pub fn can_build_on(&self, tile: (u8, u8)) -> bool {
+        !self.unbuildable.contains(&tile)
+            && self.terrain_at(tile).is_buildable()
+            && self
+                .build_pads
+                .as_ref()
+                .is_none_or(|pads| pads.contains(&tile))
+    }
+
+    /// Check the map and the tile's terrain allow this tower here (paths aside)
+    pub fn check_tower_placement(
+        &self,
+        tile: (u8, u8),
+        tower_type: TowerType,
+    ) -> Result<(), &'static str> {
+        if !self.can_build_on(tile) {
+            return Err("Cannot build on this tile");
         }
+        if !self.terrain_at(tile).allows_tower(tower_type) {
+            return Err("Only Ice and Magic towers can be built on water");
+        }
+        Ok(())
+    }
 
-        // Vertical segment going down
-        for y in 5..15 {
-            path.push((14, y));
+    /// Block or free one tile of the flow field and refresh the ground paths.
+    /// A block that cuts a spawn off from the base is undone.
+    pub fn set_blocked(&mut self, tile: (u8, u8), blocked: bool) -> Result<(), &'static str> {
+        let field = self.flow_field.as_mut().ok_or("Grid has no flow field")?;
+        field.set_blocked(Position::from_tuple(tile), blocked);
+        if self.refresh_paths().is_err() {
+            if let Some(field) = self.flow_field.as_mut() {
+                field.set_blocked(Position::from_tuple(tile), !blocked);
+            }
+            return Err("Placement would block the enemy path");
         }
+        Ok(())
+    }
 
-        // Final horizontal segment to base
-        for x in 14..20 {
-            path.push((x, 14));
+    /// Set each lane's path to the flow field's route from its spawn point
+    fn refresh_paths(&mut self) -> Result<(), &'static str> {
+        let field = self.flow_field.as_ref().ok_or("Grid has no flow field")?;
+        let paths = self
+            .lanes
+            .iter()
+            .map(|lane| field.path_from(Position::from_tuple(lane.spawn_point)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("Placement would block the enemy path")?;
+        for (lane, path) in self.lanes.iter_mut().zip(paths) {
+            lane.path = path.iter().map(Position::to_tuple).collect();
+            lane.path_to_base = lengths_to_end(&lane.path);
         }
+        Ok(())
+    }
 
-        path
+    /// Check a mazing placement leaves every spawn a route to the base
+    pub fn check_maze_placement(
+        &self,
+        position: (u8, u8),
+        blocked: &HashSet<Position>,
+    ) -> Result<(), &'static str> {
+        if position == self.base_point || self.spawn_points().contains(&position) {
+            return Err("Cannot place tower on spawn or base");
+        }
+        let cuts_off = |lane: &Lane| {
+            would_block_path(
+                Position::from_tuple(position),
+                Position::from_tuple(lane.spawn_point),
+                Position::from_tuple(self.base_point),
+                self.width,
+                self.height,
+                blocked,
+            )
+        };
+        if self.lanes.iter().any(cuts_off) {
+            return Err("Placement would block the enemy path");
+        }
+        Ok(())
     }
 
     pub fn is_on_path(&self, pos: (u8, u8)) -> bool {
-        self.path.contains(&pos)
+        self.lanes.iter().any(|lane| lane.path.contains(&pos))
     }
 
     pub fn is_valid_position(&self, pos: (u8, u8)) -> bool {
@@ -105,9 +368,63 @@ mod tests {
         let grid = Grid::new();
         assert_eq!(grid.width, 20);
         assert_eq!(grid.height, 20);
-        assert_eq!(grid.spawn_point, (0, 10));
+        assert_eq!(grid.lane(0).spawn_point, (0, 10));
         assert_eq!(grid.base_point, (19, 14));
-        assert!(!grid.path.is_empty());
+        assert!(!grid.lane(0).path.is_empty());
+    }
+
+    #[test]
+    fn test_air_route() {
+        let grid = Grid::new();
+        assert_eq!(
+            grid.route(MovementClass::Air, 0),
+            Route::Waypoints(&[(0, 10), (19, 14)])
+        );
+        assert_eq!(
+            grid.route(MovementClass::Ground, 0),
+            Route::Waypoints(&grid.lane(0).path)
+        );
+    }
+
+    #[test]
+    fn test_open_field_mazing() {
+        let mut grid = Grid::open_field();
+        assert_eq!(grid.lane(0).path.first(), Some(&grid.lane(0).spawn_point));
+        assert_eq!(grid.lane(0).path.last(), Some(&grid.base_point));
+        assert_eq!(grid.lane(0).path.len(), 20); // 19 steps, 4 of them diagonal
+
+        // A wall across column 10 with a single gap at y = 0
+        let mut blocked: HashSet<Position> = (1..20).map(|y| Position::new(10, y)).collect();
+        assert!(grid.check_maze_placement((5, 5), &blocked).is_ok());
+        assert_eq!(
+            grid.check_maze_placement((10, 0), &blocked),
+            Err("Placement would block the enemy path")
+        );
+        assert!(grid
+            .check_maze_placement(grid.base_point, &blocked)
+            .is_err());
+
+        for tile in &blocked {
+            grid.set_blocked(tile.to_tuple(), true).unwrap();
+        }
+        assert!(grid.lane(0).path.contains(&(10, 0)));
+        assert!(matches!(
+            grid.route(MovementClass::Ground, 0),
+            Route::Flow(_)
+        ));
+
+        // Closing the gap is undone; reopening a wall tile shortens the route
+        let detour = grid.lane(0).path.len();
+        assert!(grid.set_blocked((10, 0), true).is_err());
+        assert_eq!(grid.lane(0).path.len(), detour);
+        grid.set_blocked((10, 12), false).unwrap();
+        assert!(grid.lane(0).path.contains(&(10, 12)));
+        assert!(grid.lane(0).path.len() < detour);
+        blocked.remove(&Position::new(10, 12));
+
+        let rebuilt = FlowField::build(Position::from_tuple(grid.base_point), 20, 20, &blocked);
+        assert_eq!(grid.flow_field, Some(rebuilt));
+        assert!(Grid::new().set_blocked((5, 5), true).is_err());
     }
 
     #[test]
@@ -118,6 +435,27 @@ mod tests {
         assert!(!grid.is_on_path((0, 0))); // Not on path
     }
 
+    #[test]
+    fn test_terrain_placement() {
+        let mut grid = Grid::new();
+        grid.terrain.insert((3, 3), Terrain::Rock);
+        grid.terrain.insert((4, 3), Terrain::Water);
+        grid.terrain.insert((5, 3), Terrain::HighGround);
+
+        assert_eq!(
+            grid.check_tower_placement((3, 3), TowerType::Ice),
+            Err("Cannot build on this tile")
+        );
+        assert!(grid.check_tower_placement((4, 3), TowerType::Ice).is_ok());
+        assert_eq!(
+            grid.check_tower_placement((4, 3), TowerType::Cannon),
+            Err("Only Ice and Magic towers can be built on water")
+        );
+        assert!(grid
+            .check_tower_placement((5, 3), TowerType::Cannon)
+            .is_ok());
+    }
+
     #[test]
     fn test_is_valid_position() {
         let grid = Grid::new();
@@ -1,15 +1,39 @@
+pub mod fixed;
 pub mod game;
 pub mod tower;
+pub mod aura;
 pub mod enemy;
+pub mod status;
 pub mod wave;
 pub mod combat;
+pub mod projectile;
 pub mod pathfinding;
 pub mod economy;
+pub mod simulation;
+pub mod rng;
+pub mod terrain;
+pub mod map;
+pub mod mapgen;
+pub mod endless;
+pub mod difficulty;
+pub mod mutator;
 
+pub use fixed::*;
 pub use game::*;
 pub use tower::*;
+pub use aura::*;
 pub use enemy::*;
+pub use status::*;
 pub use wave::*;
 pub use combat::*;
+pub use projectile::*;
 pub use pathfinding::*;
 pub use economy::*;
+pub use simulation::*;
+pub use rng::*;
+pub use terrain::*;
+pub use map::*;
+pub use mapgen::*;
+pub use endless::*;
+pub use difficulty::*;
+pub use mutator::*;
@@ -0,0 +1,404 @@
+use crate::game::{Grid, Lane};
+use crate::terrain::Terrain;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Map limits (keep maps small enough to store on chain)
+pub const MAX_MAP_NAME_LENGTH: usize = 50;
+pub const MIN_MAP_SIZE: u8 = 4;
+pub const MAX_MAP_SIZE: u8 = 64;
+pub const MAX_LANES: usize = 8;
+pub const MAX_WAYPOINTS_PER_LANE: usize = 200;
+pub const MAX_DECORATIONS: usize = 400;
+pub const MAX_DECORATION_KIND_LENGTH: usize = 32;
+pub const MAX_MAP_ID_LENGTH: usize = 32;
+
+/// How long each featured map stays featured before the next one takes over
+pub const FEATURED_ROTATION_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+
+/// Declarative battlefield, loaded from JSON.
+///
+/// Each lane runs from its spawn point (the first waypoint) to the base (the
+/// last one). Consecutive waypoints are joined by straight or 45° lines.
+///
+/// ```json
+/// {
+///   "name": "Crossroads",
+///   "width": 16,
+///   "height": 12,
+///   "base": [15, 6],
+///   "lanes": [
+///     { "path": [[0, 2], [6, 2], [10, 6], [15, 6]] },
+///     { "path": [[0, 9], [7, 9], [10, 6], [15, 6]] }
+///   ],
+///   "unbuildable": [[12, 3]],
+///   "terrain": [{ "tile": [8, 4], "terrain": "Mud" }],
+///   "decorations": [{ "kind": "tree", "tile": [2, 5] }]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapDefinition {
+    pub name: String,
+    pub width: u8,
+    pub height: u8,
+    pub base: (u8, u8),
+    pub lanes: Vec<LaneDefinition>,
+    /// When set, towers may only be built on these tiles
+    #[serde(default)]
+    pub build_pads: Option<Vec<(u8, u8)>>,
+    /// Tiles no tower may be built on
+    #[serde(default)]
+    pub unbuildable: Vec<(u8, u8)>,
+    /// Tiles that aren't grass
+    #[serde(default)]
+    pub terrain: Vec<TerrainTile>,
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaneDefinition {
+    /// Waypoints from the spawn point to the base
+    pub path: Vec<(u8, u8)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerrainTile {
+    pub tile: (u8, u8),
+    pub terrain: Terrain,
+}
+
+/// Scenery for the frontend to draw (trees, rocks, ...); has no effect on play
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decoration {
+    pub kind: String,
+    pub tile: (u8, u8),
+}
+
+impl LaneDefinition {
+    /// Every tile the lane crosses, in order
+    pub fn tiles(&self) -> Vec<(u8, u8)> {
+        let mut tiles: Vec<(u8, u8)> = self.path.first().copied().into_iter().collect();
+        for leg in self.path.windows(2) {
+            let (mut x, mut y) = leg[0];
+            let (to_x, to_y) = leg[1];
+            while (x, y) != (to_x, to_y) {
+                x = step_towards(x, to_x);
+                y = step_towards(y, to_y);
+                tiles.push((x, y));
+            }
+        }
+        tiles
+    }
+}
+
+fn step_towards(from: u8, to: u8) -> u8 {
+    match from.cmp(&to) {
+        std::cmp::Ordering::Less => from + 1,
+        std::cmp::Ordering::Equal => from,
+        std::cmp::Ordering::Greater => from - 1,
+    }
+}
+
+/// Whether two waypoints are joined by a row, column or 45° diagonal
+fn is_straight_leg(from: (u8, u8), to: (u8, u8)) -> bool {
+    let dx = from.0.abs_diff(to.0);
+    let dy = from.1.abs_diff(to.1);
+    (dx, dy) != (0, 0) && (dx == 0 || dy == 0 || dx == dy)
+}
+
+impl MapDefinition {
+    /// The original 20x20 map with a single winding lane
+    pub fn classic() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            width: 20,
+            height: 20,
+            base: (19, 14),
+            lanes: vec![LaneDefinition {
+                path: vec![(0, 10), (7, 10), (7, 5), (14, 5), (14, 14), (19, 14)],
+            }],
+            build_pads: None,
+            unbuildable: Vec::new(),
+            terrain: Vec::new(),
+            decorations: Vec::new(),
+        }
+    }
+
+    /// Parse and validate a JSON map
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let map: Self = serde_json::from_str(json).map_err(|e| format!("Invalid map: {}", e))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Maps always serialize")
+    }
+
+    fn in_bounds(&self, tile: (u8, u8)) -> bool {
+        tile.0 < self.width && tile.1 < self.height
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.len() > MAX_MAP_NAME_LENGTH {
+            return Err(format!(
+                "Map name must be 1-{} characters",
+                MAX_MAP_NAME_LENGTH
+            ));
+        }
+        let sizes = MIN_MAP_SIZE..=MAX_MAP_SIZE;
+        if !sizes.contains(&self.width) || !sizes.contains(&self.height) {
+            return Err(format!(
+                "Map sides must be {}-{} tiles",
+                MIN_MAP_SIZE, MAX_MAP_SIZE
+            ));
+        }
+        if !self.in_bounds(self.base) {
+            return Err("Base is outside the map".to_string());
+        }
+        if self.lanes.is_empty() || self.lanes.len() > MAX_LANES {
+            return Err(format!("Map must have 1-{} lanes", MAX_LANES));
+        }
+
+        for (index, lane) in self.lanes.iter().enumerate() {
+            if lane.path.len() < 2 || lane.path.len() > MAX_WAYPOINTS_PER_LANE {
+                return Err(format!(
+                    "Lane {}: path must have 2-{} waypoints",
+                    index, MAX_WAYPOINTS_PER_LANE
+                ));
+            }
+            if let Some(tile) = lane.path.iter().find(|tile| !self.in_bounds(**tile)) {
+                return Err(format!("Lane {}: {:?} is outside the map", index, tile));
+            }
+            if let Some(leg) = lane
+                .path
+                .windows(2)
+                .find(|leg| !is_straight_leg(leg[0], leg[1]))
+            {
+                return Err(format!(
+                    "Lane {}: {:?} to {:?} is not a straight or diagonal line",
+                    index, leg[0], leg[1]
+                ));
+            }
+            if lane.path.last() != Some(&self.base) {
+                return Err(format!("Lane {}: path must end at the base", index));
+            }
+        }
+
+        if let Some(tile) = self.unbuildable.iter().find(|tile| !self.in_bounds(**tile)) {
+            return Err(format!("Unbuildable tile {:?} is outside the map", tile));
+        }
+        if let Some(pads) = &self.build_pads {
+            let path_tiles: BTreeSet<(u8, u8)> =
+                self.lanes.iter().flat_map(LaneDefinition::tiles).collect();
+            for pad in pads {
+                if !self.in_bounds(*pad) {
+                    return Err(format!("Build pad {:?} is outside the map", pad));
+                }
+                if path_tiles.contains(pad) {
+                    return Err(format!("Build pad {:?} is on a path", pad));
+                }
+            }
+        }
+        if let Some(tile) = self.terrain.iter().find(|tile| !self.in_bounds(tile.tile)) {
+            return Err(format!("Terrain tile {:?} is outside the map", tile.tile));
+        }
+
+        if self.decorations.len() > MAX_DECORATIONS {
+            return Err(format!(
+                "Map may have at most {} decorations",
+                MAX_DECORATIONS
+            ));
+        }
+        for decoration in &self.decorations {
+            if decoration.kind.is_empty() || decoration.kind.len() > MAX_DECORATION_KIND_LENGTH {
+                return Err(format!(
+                    "Decoration kind must be 1-{} characters",
+                    MAX_DECORATION_KIND_LENGTH
+                ));
+            }
+            if !self.in_bounds(decoration.tile) {
+                return Err(format!(
+                    "Decoration {:?} is outside the map",
+                    decoration.tile
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lay out the battlefield (expects a validated map)
+    pub fn to_grid(&self) -> Grid {
+        Grid {
+            width: self.width,
+            height: self.height,
+            lanes: self
+                .lanes
+                .iter()
+                .map(|lane| Lane::new(lane.tiles(), self.base))
+                .collect(),
+            base_point: self.base,
+            flow_field: None,
+            terrain: self
+                .terrain
+                .iter()
+                .map(|tile| (tile.tile, tile.terrain))
+                .collect(),
+            unbuildable: self.unbuildable.iter().copied().collect(),
+            build_pads: self
+                .build_pads
+                .as_ref()
+                .map(|pads| pads.iter().copied().collect()),
+            decorations: self.decorations.clone(),
+        }
+    }
+}
+
+/// Registry IDs are short lowercase slugs, e.g. "crossroads" or "twin-peaks"
+pub fn validate_map_id(map_id: &str) -> Result<(), String> {
+    if map_id.is_empty() || map_id.len() > MAX_MAP_ID_LENGTH {
+        return Err(format!("Map ID must be 1-{} characters", MAX_MAP_ID_LENGTH));
+    }
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+    if !map_id.chars().all(allowed) {
+        return Err("Map ID may only use a-z, 0-9 and '-'".to_string());
+    }
+    Ok(())
+}
+
+/// The featured map for the week containing `now_micros`; cycles through `pool`
+pub fn featured_map(pool: &[String], now_micros: u64) -> Option<&String> {
+    if pool.is_empty() {
+        return None;
+    }
+    let week = now_micros / FEATURED_ROTATION_MICROS;
+    pool.get((week % pool.len() as u64) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classic_map() {
+        let map = MapDefinition::classic();
+        assert!(map.validate().is_ok());
+
+        let tiles = map.lanes[0].tiles();
+        assert_eq!(tiles.len(), 34);
+        assert_eq!(tiles.first(), Some(&(0, 10)));
+        assert_eq!(tiles.last(), Some(&(19, 14)));
+        let adjacent = |a: (u8, u8), b: (u8, u8)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1) == 1;
+        assert!(tiles.windows(2).all(|step| adjacent(step[0], step[1])));
+    }
+
+    #[test]
+    fn test_crossroads_map() {
+        let map = MapDefinition::from_json(include_str!("../maps/crossroads.json")).unwrap();
+        assert_eq!(map.name, "Crossroads");
+        assert_eq!(map.lanes.len(), 2);
+
+        let grid = map.to_grid();
+        assert_eq!(grid.lanes.len(), 2);
+        assert_eq!(grid.lane(1).spawn_point, (0, 9));
+        assert_ne!(grid.lane(0).spawn_point, grid.lane(1).spawn_point);
+        assert_eq!(grid.lane(1).path.last(), Some(&grid.base_point));
+        assert_eq!(grid.lane(1).air_path, vec![(0, 9), (15, 6)]);
+        // Lanes past the last one fall back to lane 0
+        assert_eq!(grid.lane(7), grid.lane(0));
+
+        assert!(grid.is_on_path((8, 4))); // Lane 0's diagonal
+        assert!(grid.is_on_path((5, 9))); // Lane 1 only
+        assert_eq!(grid.terrain_at((8, 4)), Terrain::Mud);
+        assert!(!grid.can_build_on((12, 3)));
+        assert!(grid.can_build_on((12, 8)));
+        assert_eq!(grid.decorations.len(), 3);
+
+        assert_eq!(MapDefinition::from_json(&map.to_json()).unwrap(), map);
+    }
+
+    #[test]
+    fn test_build_pads() {
+        let mut map = MapDefinition::classic();
+        map.build_pads = Some(vec![(3, 3), (8, 8)]);
+        let grid = map.to_grid();
+        assert!(grid.can_build_on((3, 3)));
+        assert!(!grid.can_build_on((4, 3)));
+
+        map.build_pads = Some(vec![(3, 10)]);
+        assert_eq!(
+            map.validate(),
+            Err("Build pad (3, 10) is on a path".to_string())
+        );
+    }
+
+    #[test]
+    fn test_map_validation() {
+        let invalid = |edit: fn(&mut MapDefinition)| {
+            let mut map = MapDefinition::classic();
+            edit(&mut map);
+            map.validate().unwrap_err()
+        };
+
+        assert_eq!(
+            invalid(|map| map.width = 100),
+            "Map sides must be 4-64 tiles"
+        );
+        assert_eq!(invalid(|map| map.base = (20, 0)), "Base is outside the map");
+        assert_eq!(invalid(|map| map.lanes.clear()), "Map must have 1-8 lanes");
+        assert_eq!(
+            invalid(|map| map.lanes[0].path[0] = (0, 30)),
+            "Lane 0: (0, 30) is outside the map"
+        );
+        assert_eq!(
+            invalid(|map| map.lanes[0].path[1] = (7, 9)),
+            "Lane 0: (0, 10) to (7, 9) is not a straight or diagonal line"
+        );
+        assert_eq!(
+            invalid(|map| map.lanes[0].path.insert(1, (0, 10))),
+            "Lane 0: (0, 10) to (0, 10) is not a straight or diagonal line"
+        );
+        assert_eq!(
+            invalid(|map| map.lanes[0].path.truncate(5)),
+            "Lane 0: path must end at the base"
+        );
+        assert_eq!(
+            invalid(|map| map.unbuildable.push((25, 1))),
+            "Unbuildable tile (25, 1) is outside the map"
+        );
+        assert_eq!(
+            invalid(|map| map.decorations.push(Decoration {
+                kind: String::new(),
+                tile: (1, 1),
+            })),
+            "Decoration kind must be 1-32 characters"
+        );
+        assert!(MapDefinition::from_json("{\"name\": \"Empty\"}").is_err());
+    }
+
+    #[test]
+    fn test_map_ids_and_rotation() {
+        assert!(validate_map_id("twin-peaks-2").is_ok());
+        assert!(validate_map_id("").is_err());
+        assert!(validate_map_id("Twin Peaks").is_err());
+        assert!(validate_map_id(&"a".repeat(33)).is_err());
+
+        let pool = vec!["classic".to_string(), "crossroads".to_string()];
+        assert_eq!(featured_map(&pool, 0), Some(&pool[0]));
+        assert_eq!(
+            featured_map(&pool, FEATURED_ROTATION_MICROS - 1),
+            Some(&pool[0])
+        );
+        assert_eq!(
+            featured_map(&pool, FEATURED_ROTATION_MICROS),
+            Some(&pool[1])
+        );
+        assert_eq!(
+            featured_map(&pool, 2 * FEATURED_ROTATION_MICROS),
+            Some(&pool[0])
+        );
+        assert_eq!(featured_map(&[], 0), None);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::map::{LaneDefinition, MapDefinition, MAX_LANES, MAX_MAP_SIZE, MIN_MAP_SIZE};
+use crate::rng::GameRng;
+use serde::{Deserialize, Serialize};
+
+/// Most turns a procedural lane may take
+pub const MAX_TURNS: u8 = 16;
+
+/// Layouts tried per lane before giving up on a seed
+const MAX_ATTEMPTS: u32 = 256;
+
+/// Settings and seed of a procedural map; the same pair always lays out the same map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProceduralMap {
+    pub seed: u64,
+    pub width: u8,
+    pub height: u8,
+    pub lanes: u8,
+    /// Corners in each lane's path
+    pub turns: u8,
+    /// Allowed lane length in tiles, spawn and base included
+    pub min_length: u32,
+    pub max_length: u32,
+}
+
+impl ProceduralMap {
+    /// A single-lane 20x20 map with the given seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            width: 20,
+            height: 20,
+            lanes: 1,
+            turns: 4,
+            min_length: 30,
+            max_length: 60,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let sizes = MIN_MAP_SIZE..=MAX_MAP_SIZE;
+        if !sizes.contains(&self.width) || !sizes.contains(&self.height) {
+            return Err(format!(
+                "Map sides must be {}-{} tiles",
+                MIN_MAP_SIZE, MAX_MAP_SIZE
+            ));
+        }
+        if self.lanes == 0 || self.lanes as usize > MAX_LANES || self.lanes > self.height {
+            return Err(format!(
+                "Procedural maps must have 1-{} lanes, one spawn row each",
+                MAX_LANES
+            ));
+        }
+        if self.turns > MAX_TURNS {
+            return Err(format!("Lanes may take at most {} turns", MAX_TURNS));
+        }
+        if self.turns == 0 && self.lanes > 1 {
+            return Err("Straight lanes would all share the base's row".to_string());
+        }
+        // Vertical runs each need their own column between the edges
+        if self.turns / 2 > self.width - 2 {
+            return Err("Map is too narrow for that many turns".to_string());
+        }
+        if self.min_length > self.max_length {
+            return Err("min_length must not exceed max_length".to_string());
+        }
+        Ok(())
+    }
+
+    /// Lay out the map; fails if no layout for this seed fits the length band
+    pub fn generate(&self) -> Result<MapDefinition, String> {
+        self.validate()?;
+        let mut rng = GameRng::new(self.seed);
+        let base = (self.width - 1, rng.below(self.height as u32) as u8);
+
+        let mut free_rows: Vec<u8> = (0..self.height).collect();
+        let mut lanes = Vec::new();
+        for index in 0..self.lanes {
+            let lane = (0..MAX_ATTEMPTS)
+                .find_map(|_| self.try_lane(base, &free_rows, &mut rng))
+                .ok_or_else(|| format!("Lane {}: no layout fits the length band", index))?;
+            free_rows.retain(|row| *row != lane.path[0].1);
+            lanes.push(lane);
+        }
+
+        let map = MapDefinition {
+            name: format!("Procedural {}", self.seed),
+            width: self.width,
+            height: self.height,
+            base,
+            lanes,
+            build_pads: None,
+            unbuildable: Vec::new(),
+            terrain: Vec::new(),
+            decorations: Vec::new(),
+        };
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// One random lane from the left edge to the base, alternating horizontal
+    /// and vertical runs. Runs only ever head right, so the path never crosses itself.
+    fn try_lane(
+        &self,
+        base: (u8, u8),
+        free_rows: &[u8],
+        rng: &mut GameRng,
+    ) -> Option<LaneDefinition> {
+        let verticals = self.turns.div_ceil(2) as usize;
+        // An odd turn count climbs the last column into the base
+        let ends_vertically = self.turns % 2 == 1;
+
+        let spawn_row = if verticals == 0 {
+            base.1
+        } else {
+            free_rows[rng.below(free_rows.len() as u32) as usize]
+        };
+        if !free_rows.contains(&spawn_row) {
+            return None;
+        }
+
+        // Distinct columns for the vertical runs, picked with a partial shuffle
+        let mut columns: Vec<u8> = (1..base.0).collect();
+        let picked = verticals - ends_vertically as usize;
+        for i in 0..picked {
+            let j = i + rng.below((columns.len() - i) as u32) as usize;
+            columns.swap(i, j);
+        }
+        columns.truncate(picked);
+        columns.sort_unstable();
+        if ends_vertically {
+            columns.push(base.0);
+        }
+
+        let mut path = vec![(0, spawn_row)];
+        let mut row = spawn_row;
+        for (index, column) in columns.iter().enumerate() {
+            let next_row = if index + 1 == verticals {
+                base.1
+            } else {
+                rng.below(self.height as u32) as u8
+            };
+            if next_row == row {
+                return None;
+            }
+            path.push((*column, row));
+            path.push((*column, next_row));
+            row = next_row;
+        }
+        if !ends_vertically {
+            path.push(base);
+        }
+
+        let lane = LaneDefinition { path };
+        let length = lane.tiles().len() as u32;
+        if length < self.min_length || length > self.max_length {
+            return None;
+        }
+        Some(lane)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::{find_path, Position};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_same_seed_same_map() {
+        let map = ProceduralMap::new(7).generate().unwrap();
+        assert_eq!(ProceduralMap::new(7).generate().unwrap(), map);
+        assert_ne!(ProceduralMap::new(8).generate().unwrap(), map);
+    }
+
+    #[test]
+    fn test_generated_maps_fit_the_settings() {
+        for seed in 0..50 {
+            let settings = ProceduralMap {
+                lanes: 3,
+                turns: 5,
+                min_length: 25,
+                max_length: 70,
+                ..ProceduralMap::new(seed)
+            };
+            let map = settings.generate().unwrap();
+            assert_eq!(map.lanes.len(), 3);
+
+            let grid = map.to_grid();
+            let spawns: HashSet<(u8, u8)> = grid.spawn_points().into_iter().collect();
+            assert_eq!(spawns.len(), 3);
+            for (definition, lane) in map.lanes.iter().zip(&grid.lanes) {
+                assert!((25..=70).contains(&lane.path.len()));
+                assert_eq!(lane.path.last(), Some(&grid.base_point));
+                // The lane is one unbroken walk: the pathfinder gets from
+                // spawn to base on its tiles alone
+                let open: HashSet<(u8, u8)> = lane.path.iter().copied().collect();
+                let off_lane: HashSet<Position> = (0..grid.width)
+                    .flat_map(|x| (0..grid.height).map(move |y| (x, y)))
+                    .filter(|tile| !open.contains(tile))
+                    .map(Position::from_tuple)
+                    .collect();
+                let walk = find_path(
+                    Position::from_tuple(lane.spawn_point),
+                    Position::from_tuple(grid.base_point),
+                    grid.width,
+                    grid.height,
+                    &off_lane,
+                );
+                assert!(walk.is_some());
+                assert!(lane.path.windows(2).all(|pair| {
+                    let (a, b) = (Position::from_tuple(pair[0]), Position::from_tuple(pair[1]));
+                    a.manhattan_distance(&b) == 1
+                }));
+                // Spawn, a waypoint per turn, and the base
+                assert_eq!(definition.path.len(), 7);
+            }
+        }
+    }
+
+    #[test]
+    fn test_impossible_settings() {
+        let too_long = ProceduralMap {
+            min_length: 500,
+            max_length: 600,
+            ..ProceduralMap::new(1)
+        };
+        assert_eq!(
+            too_long.generate(),
+            Err("Lane 0: no layout fits the length band".to_string())
+        );
+
+        let straight = ProceduralMap {
+            turns: 0,
+            lanes: 2,
+            ..ProceduralMap::new(1)
+        };
+        assert!(straight.generate().is_err());
+
+        let cramped = ProceduralMap {
+            width: 4,
+            turns: 9,
+            ..ProceduralMap::new(1)
+        };
+        assert_eq!(
+            cramped.validate(),
+            Err("Map is too narrow for that many turns".to_string())
+        );
+    }
+}
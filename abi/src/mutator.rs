@@ -0,0 +1,208 @@
+use crate::endless::EndlessMutator;
+use crate::enemy::Enemy;
+use crate::fixed::apply_permille;
+use crate::map::FEATURED_ROTATION_MICROS;
+use crate::tower::Tower;
+use serde::{Deserialize, Serialize};
+
+/// Enemy speed under `Swift`, per-mille
+pub const SWIFT_SPEED_PERMILLE: u32 = 1300;
+/// Tower prices under `DoubleCost`, in percent
+pub const DOUBLE_COST_PERCENT: u64 = 200;
+/// Tiles of range towers lose under `Fog`
+pub const FOG_RANGE_PENALTY: u8 = 1;
+
+/// Rule change for a whole game, or for single waves of its script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Mutator {
+    /// Enemies move 30% faster
+    Swift,
+    /// Enemies carry 10 extra armor
+    Armored,
+    /// Towers can't be sold
+    NoSelling,
+    /// Towers, upgrades and specializations cost double
+    DoubleCost,
+    /// Towers reach one tile less (never below 1)
+    Fog,
+    /// Enemies spawn with a shield worth 20% of max health
+    Shielded,
+    /// Enemies without an ability regenerate 1% of max health per second
+    Regenerating,
+    /// Enemies gain 5% dodge chance
+    Evasive,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 8] = [
+        Self::Swift,
+        Self::Armored,
+        Self::NoSelling,
+        Self::DoubleCost,
+        Self::Fog,
+        Self::Shielded,
+        Self::Regenerating,
+        Self::Evasive,
+    ];
+
+    /// Whether a wave script may switch this on for a single wave.
+    /// Economy rules only make sense for a whole game.
+    pub fn per_wave(&self) -> bool {
+        !matches!(self, Self::NoSelling | Self::DoubleCost)
+    }
+
+    /// The endless-mode upgrade this mutator shares its rule with
+    pub fn endless(&self) -> Option<EndlessMutator> {
+        match self {
+            Self::Armored => Some(EndlessMutator::Armored),
+            Self::Shielded => Some(EndlessMutator::Shielded),
+            Self::Regenerating => Some(EndlessMutator::Regenerating),
+            Self::Evasive => Some(EndlessMutator::Evasive),
+            Self::Swift | Self::NoSelling | Self::DoubleCost | Self::Fog => None,
+        }
+    }
+
+    /// Adjust a freshly spawned enemy
+    pub fn apply_to_enemy(&self, enemy: &mut Enemy) {
+        match self.endless() {
+            Some(endless) => endless.apply(enemy),
+            None if *self == Self::Swift => {
+                enemy.speed = apply_permille(enemy.speed, SWIFT_SPEED_PERMILLE)
+            }
+            None => {}
+        }
+    }
+}
+
+impl From<EndlessMutator> for Mutator {
+    fn from(mutator: EndlessMutator) -> Self {
+        match mutator {
+            EndlessMutator::Armored => Self::Armored,
+            EndlessMutator::Shielded => Self::Shielded,
+            EndlessMutator::Regenerating => Self::Regenerating,
+            EndlessMutator::Evasive => Self::Evasive,
+        }
+    }
+}
+
+/// Reject repeated mutators (and, for single waves, economy ones)
+pub fn validate_mutators(mutators: &[Mutator], per_wave: bool) -> Result<(), String> {
+    for (index, mutator) in mutators.iter().enumerate() {
+        if mutators[..index].contains(mutator) {
+            return Err(format!("Mutator {:?} is listed twice", mutator));
+        }
+        if per_wave && !mutator.per_wave() {
+            return Err(format!(
+                "Mutator {:?} can only apply to a whole game",
+                mutator
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Apply every enemy mutator to a freshly spawned enemy
+pub fn apply_enemy_mutators(enemy: &mut Enemy, mutators: &[Mutator]) {
+    for mutator in mutators {
+        mutator.apply_to_enemy(enemy);
+    }
+}
+
+/// Cut tower ranges after auras have been applied
+pub fn apply_tower_mutators(towers: &mut [Tower], mutators: &[Mutator]) {
+    if !mutators.contains(&Mutator::Fog) {
+        return;
+    }
+    for tower in towers.iter_mut().filter(|t| t.stats.range > 0) {
+        tower.stats.range = tower.stats.range.saturating_sub(FOG_RANGE_PENALTY).max(1);
+    }
+}
+
+/// A price (tower, upgrade or specialization) under the game's mutators
+pub fn mutated_cost(cost: u64, mutators: &[Mutator]) -> u64 {
+    if mutators.contains(&Mutator::DoubleCost) {
+        cost.saturating_mul(DOUBLE_COST_PERCENT) / 100
+    } else {
+        cost
+    }
+}
+
+/// Whether towers may be sold under the game's mutators
+pub fn selling_allowed(mutators: &[Mutator]) -> bool {
+    !mutators.contains(&Mutator::NoSelling)
+}
+
+/// Week number of a timestamp, which identifies its weekly challenge
+pub fn challenge_week(now_micros: u64) -> u64 {
+    now_micros / FEATURED_ROTATION_MICROS
+}
+
+/// A week's challenge: a pair of mutators, rotating weekly through every
+/// pair in `Mutator::ALL`
+pub fn challenge_mutators(week: u64) -> Vec<Mutator> {
+    let mut combinations = Vec::new();
+    for (index, first) in Mutator::ALL.iter().enumerate() {
+        for second in &Mutator::ALL[index + 1..] {
+            combinations.push(vec![*first, *second]);
+        }
+    }
+    combinations.swap_remove((week % combinations.len() as u64) as usize)
+}
+
+/// This week's challenge mutators
+pub fn weekly_challenge(now_micros: u64) -> Vec<Mutator> {
+    challenge_mutators(challenge_week(now_micros))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endless::ARMORED_BONUS;
+    use crate::enemy::EnemyType;
+    use crate::tower::TowerType;
+
+    #[test]
+    fn test_mutators_compose() {
+        let mutators = [Mutator::Swift, Mutator::Armored, Mutator::DoubleCost];
+        let mut enemy = Enemy::new(0, EnemyType::BasicScout, 1, (0, 0));
+        apply_enemy_mutators(&mut enemy, &mutators);
+        assert_eq!(enemy.speed, 1300);
+        assert_eq!(enemy.armor, ARMORED_BONUS);
+
+        assert_eq!(mutated_cost(TowerType::Arrow.cost(), &mutators), 200);
+        assert_eq!(mutated_cost(TowerType::Arrow.cost(), &[]), 100);
+        assert!(selling_allowed(&mutators));
+        assert!(!selling_allowed(&[Mutator::NoSelling]));
+
+        let mut towers = vec![Tower::new(0, (5, 5), TowerType::Arrow, 0)];
+        towers[0].stats.range = 1;
+        apply_tower_mutators(&mut towers, &[Mutator::Fog]);
+        assert_eq!(towers[0].stats.range, 1);
+        towers[0].stats.range = 3;
+        apply_tower_mutators(&mut towers, &[Mutator::Fog]);
+        assert_eq!(towers[0].stats.range, 2);
+    }
+
+    #[test]
+    fn test_validate_mutators() {
+        assert!(validate_mutators(&[Mutator::Fog, Mutator::NoSelling], false).is_ok());
+        assert_eq!(
+            validate_mutators(&[Mutator::Fog, Mutator::Fog], false),
+            Err("Mutator Fog is listed twice".to_string())
+        );
+        assert!(validate_mutators(&[Mutator::Swift, Mutator::Fog], true).is_ok());
+        assert!(validate_mutators(&[Mutator::DoubleCost], true).is_err());
+    }
+
+    #[test]
+    fn test_weekly_challenge_rotates() {
+        let week = FEATURED_ROTATION_MICROS;
+        let first = weekly_challenge(0);
+        assert_eq!(first.len(), 2);
+        assert_eq!(weekly_challenge(week - 1), first);
+        assert_ne!(weekly_challenge(week), first);
+        assert_eq!(challenge_week(week), 1);
+        assert_eq!(challenge_mutators(1), weekly_challenge(week));
+        assert!(validate_mutators(&first, false).is_ok());
+    }
+}
@@ -1,6 +1,28 @@
+use crate::fixed::{MILLI_PER_TILE, PERMILLE};
+use crate::terrain::{terrain_at, Terrain, TerrainMap};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
+/// Flow field distance of a tile that is blocked or cut off from the goal
+pub const UNREACHABLE: u32 = u32::MAX;
+
+/// Route cost of a straight step onto plain ground
+pub const STEP_COST: u32 = 1000;
+
+/// Route cost of a diagonal step onto plain ground (about sqrt(2) straight steps)
+pub const DIAGONAL_STEP_COST: u32 = 1414;
+
+/// Route cost of one step onto a tile whose terrain costs `tile_cost_permille`
+pub fn step_cost(diagonal: bool, tile_cost_permille: u32) -> u32 {
+    let base = if diagonal {
+        DIAGONAL_STEP_COST
+    } else {
+        STEP_COST
+    };
+    base * tile_cost_permille / PERMILLE
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Position {
     pub x: u8,
@@ -56,6 +78,47 @@ impl Position {
 
         neighbors
     }
+
+    /// Route cost of the shortest 8-connected walk to `other` on plain ground
+    pub fn octile_distance(&self, other: &Position) -> u32 {
+        let dx = self.x.abs_diff(other.x) as u32;
+        let dy = self.y.abs_diff(other.y) as u32;
+        let diagonal = dx.min(dy);
+        diagonal * DIAGONAL_STEP_COST + (dx.max(dy) - diagonal) * STEP_COST
+    }
+
+    /// 8-connected moves onto free tiles, as `(tile, is_diagonal)`, straight
+    /// moves first. A diagonal may not cut a corner: both tiles beside it must
+    /// be free as well.
+    pub fn moves(
+        &self,
+        width: u8,
+        height: u8,
+        is_blocked: impl Fn(Position) -> bool,
+    ) -> Vec<(Position, bool)> {
+        let mut moves: Vec<(Position, bool)> = self
+            .neighbors(width, height)
+            .into_iter()
+            .filter(|n| !is_blocked(*n))
+            .map(|n| (n, false))
+            .collect();
+
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let x = self.x as i16 + dx;
+            let y = self.y as i16 + dy;
+            if x < 0 || y < 0 || x >= width as i16 || y >= height as i16 {
+                continue;
+            }
+            let (x, y) = (x as u8, y as u8);
+            let corners_free =
+                !is_blocked(Position::new(x, self.y)) && !is_blocked(Position::new(self.x, y));
+            if corners_free && !is_blocked(Position::new(x, y)) {
+                moves.push((Position::new(x, y), true));
+            }
+        }
+
+        moves
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -86,6 +149,22 @@ pub fn find_path(
     height: u8,
     blocked: &HashSet<Position>,
 ) -> Option<Vec<Position>> {
+    find_path_on_terrain(start, goal, width, height, blocked, &TerrainMap::new())
+}
+
+/// A* over 8-connected moves, each step weighted by the terrain it enters
+pub fn find_path_on_terrain(
+    start: Position,
+    goal: Position,
+    width: u8,
+    height: u8,
+    blocked: &HashSet<Position>,
+    terrain: &TerrainMap,
+) -> Option<Vec<Position>> {
+    // Octile distance at the cheapest terrain never overestimates
+    let cheapest = Terrain::cheapest_cost_permille();
+    let heuristic = |p: Position| p.octile_distance(&goal) * cheapest / PERMILLE;
+
     let mut open_set = BinaryHeap::new();
     let mut came_from: HashMap<Position, Position> = HashMap::new();
     let mut g_scores: HashMap<Position, u32> = HashMap::new();
@@ -93,7 +172,7 @@ pub fn find_path(
     g_scores.insert(start, 0);
     open_set.push(Node {
         position: start,
-        f_score: start.manhattan_distance(&goal),
+        f_score: heuristic(start),
         g_score: 0,
     });
 
@@ -106,20 +185,20 @@ pub fn find_path(
 
         let current_g_score = *g_scores.get(&current).unwrap_or(&u32::MAX);
 
-        for neighbor in current.neighbors(width, height) {
-            // Skip blocked positions
-            if blocked.contains(&neighbor) {
-                continue;
-            }
-
-            let tentative_g_score = current_g_score.saturating_add(1);
+        // Blocked tiles are never offered as moves
+        for (neighbor, diagonal) in current.moves(width, height, |p| blocked.contains(&p)) {
+            let cost = step_cost(
+                diagonal,
+                terrain_at(terrain, neighbor.to_tuple()).cost_permille(),
+            );
+            let tentative_g_score = current_g_score.saturating_add(cost);
             let neighbor_g_score = *g_scores.get(&neighbor).unwrap_or(&u32::MAX);
 
             if tentative_g_score < neighbor_g_score {
                 came_from.insert(neighbor, current);
                 g_scores.insert(neighbor, tentative_g_score);
 
-                let f_score = tentative_g_score + neighbor.manhattan_distance(&goal);
+                let f_score = tentative_g_score + heuristic(neighbor);
                 open_set.push(Node {
                     position: neighbor,
                     f_score,
@@ -159,6 +238,268 @@ pub fn would_block_path(
     find_path(start, goal, width, height, &blocked).is_none()
 }
 
+/// Cost of reaching the goal from every tile, searched outward from the goal.
+///
+/// Any number of enemies walk downhill on the same field, so the grid is
+/// searched once per change instead of once per enemy. Blocking or freeing a
+/// single tile only repairs the tiles whose distance actually changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowField {
+    pub width: u8,
+    pub height: u8,
+    pub goal: Position,
+    /// Row-major route cost to the goal, UNREACHABLE for blocked or cut-off tiles
+    distances: Vec<u32>,
+    blocked: Vec<bool>,
+    /// Row-major terrain cost of entering each tile, per-mille
+    costs: Vec<u32>,
+}
+
+impl FlowField {
+    pub fn build(goal: Position, width: u8, height: u8, blocked: &HashSet<Position>) -> Self {
+        Self::build_on_terrain(goal, width, height, blocked, &TerrainMap::new())
+    }
+
+    pub fn build_on_terrain(
+        goal: Position,
+        width: u8,
+        height: u8,
+        blocked: &HashSet<Position>,
+        terrain: &TerrainMap,
+    ) -> Self {
+        let tiles = width as usize * height as usize;
+        let mut field = Self {
+            width,
+            height,
+            goal,
+            distances: vec![UNREACHABLE; tiles],
+            blocked: vec![false; tiles],
+            costs: vec![PERMILLE; tiles],
+        };
+        for index in 0..tiles {
+            let tile = field.position(index).to_tuple();
+            field.costs[index] = terrain_at(terrain, tile).cost_permille();
+        }
+        for position in blocked {
+            if let Some(index) = field.index(*position) {
+                field.blocked[index] = true;
+            }
+        }
+        if let Some(goal_index) = field.index(goal) {
+            field.relax(BinaryHeap::from([Reverse((0, goal_index))]));
+        }
+        field
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        (position.x < self.width && position.y < self.height)
+            .then(|| position.y as usize * self.width as usize + position.x as usize)
+    }
+
+    fn position(&self, index: usize) -> Position {
+        let width = self.width as usize;
+        Position::new((index % width) as u8, (index / width) as u8)
+    }
+
+    /// Route cost from `position` to the goal, or None if it cannot get there
+    pub fn distance(&self, position: Position) -> Option<u32> {
+        let index = self.index(position)?;
+        Some(self.distances[index]).filter(|d| *d != UNREACHABLE)
+    }
+
+    pub fn is_blocked(&self, position: Position) -> bool {
+        self.index(position)
+            .is_some_and(|index| self.blocked[index])
+    }
+
+    /// Moves out of `position` with the cost of each
+    fn moves(&self, position: Position) -> Vec<(Position, u32)> {
+        position
+            .moves(self.width, self.height, |p| self.is_blocked(p))
+            .into_iter()
+            .filter_map(|(to, diagonal)| {
+                let cost = self.costs[self.index(to)?];
+                Some((to, step_cost(diagonal, cost)))
+            })
+            .collect()
+    }
+
+    /// Neighbour on a cheapest route to the goal (None at the goal or when cut off)
+    pub fn next_step(&self, position: Position) -> Option<Position> {
+        let distance = self.distance(position)?;
+        if position == self.goal {
+            return None;
+        }
+        self.moves(position)
+            .into_iter()
+            .find(|(to, cost)| self.distance(*to).map(|d| d + cost) == Some(distance))
+            .map(|(to, _)| to)
+    }
+
+    /// The route an enemy standing on `start` walks to the goal
+    pub fn path_from(&self, start: Position) -> Option<Vec<Position>> {
+        self.distance(start)?;
+        let mut path = vec![start];
+        let mut current = start;
+        while let Some(next) = self.next_step(current) {
+            path.push(next);
+            current = next;
+        }
+        Some(path)
+    }
+
+    /// Tile an enemy at a milli-tile position should head to next.
+    /// On a tile centre that is the next step downhill; between tiles it is the
+    /// closest surrounding tile to the goal, so an enemy walks back off a tile
+    /// that was blocked in front of it. None when the enemy is cut off.
+    pub fn next_tile_from(&self, position: (i32, i32)) -> Option<Position> {
+        let spans = |v: i32| {
+            let v = v.max(0);
+            let low = (v / MILLI_PER_TILE).min(u8::MAX as i32) as u8;
+            let high = if v % MILLI_PER_TILE == 0 {
+                low
+            } else {
+                low.saturating_add(1)
+            };
+            (low, high)
+        };
+        let (x_low, x_high) = spans(position.0);
+        let (y_low, y_high) = spans(position.1);
+
+        if x_low == x_high && y_low == y_high {
+            return self.next_step(Position::new(x_low, y_low));
+        }
+        [
+            Position::new(x_low, y_low),
+            Position::new(x_high, y_low),
+            Position::new(x_low, y_high),
+            Position::new(x_high, y_high),
+        ]
+        .into_iter()
+        .filter_map(|p| self.distance(p).map(|d| (d, p)))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, p)| p)
+    }
+
+    /// Block or free one tile, repairing only the distances that change
+    pub fn set_blocked(&mut self, position: Position, blocked: bool) {
+        let Some(index) = self.index(position) else {
+            return;
+        };
+        if self.blocked[index] == blocked {
+            return;
+        }
+        self.blocked[index] = blocked;
+
+        // Every tile around `position` may step through it or past its corners
+        let surrounding: Vec<Position> = position
+            .moves(self.width, self.height, |_| false)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        if !blocked {
+            let mut heap = BinaryHeap::new();
+            if position == self.goal {
+                heap.push(Reverse((0, index)));
+            }
+            for tile in std::iter::once(position).chain(surrounding) {
+                if let (Some(cost), Some(tile_index)) = (self.best_route(tile), self.index(tile)) {
+                    heap.push(Reverse((cost, tile_index)));
+                }
+            }
+            self.relax(heap);
+            return;
+        }
+
+        let old = std::mem::replace(&mut self.distances[index], UNREACHABLE);
+        if old == UNREACHABLE {
+            return;
+        }
+
+        // Cut off every tile left without a route of its current cost. Tiles are
+        // checked cheapest first, so the tiles they could lean on are already
+        // settled; each cut re-queues its neighbours for another check.
+        let mut queue = BinaryHeap::new();
+        for tile in surrounding {
+            if let (Some(cost), Some(tile_index)) = (self.distance(tile), self.index(tile)) {
+                queue.push(Reverse((cost, tile_index)));
+            }
+        }
+        let mut invalidated = Vec::new();
+        while let Some(Reverse((cost, tile_index))) = queue.pop() {
+            let tile = self.position(tile_index);
+            if self.distances[tile_index] != cost
+                || tile == self.goal
+                || self.best_route(tile) == Some(cost)
+            {
+                continue;
+            }
+            self.distances[tile_index] = UNREACHABLE;
+            invalidated.push((tile, tile_index));
+            for (neighbor, _) in tile.moves(self.width, self.height, |_| false) {
+                if let (Some(cost), Some(neighbor_index)) =
+                    (self.distance(neighbor), self.index(neighbor))
+                {
+                    queue.push(Reverse((cost, neighbor_index)));
+                }
+            }
+        }
+
+        // Re-seed the cut-off tiles from their surviving neighbours
+        let mut heap = BinaryHeap::new();
+        for (tile, tile_index) in invalidated {
+            if let Some(cost) = self.best_route(tile) {
+                heap.push(Reverse((cost, tile_index)));
+            }
+        }
+        self.relax(heap);
+    }
+
+    /// Cheapest route cost from `position` through one of its neighbours
+    fn best_route(&self, position: Position) -> Option<u32> {
+        if self.is_blocked(position) {
+            return None;
+        }
+        self.moves(position)
+            .into_iter()
+            .filter_map(|(to, cost)| self.distance(to).map(|d| d + cost))
+            .min()
+    }
+
+    /// Lower distances outward from the seeded tiles (Dijkstra)
+    fn relax(&mut self, mut heap: BinaryHeap<Reverse<(u32, usize)>>) {
+        while let Some(Reverse((cost, index))) = heap.pop() {
+            if self.blocked[index] || cost >= self.distances[index] {
+                continue;
+            }
+            self.distances[index] = cost;
+
+            // Moves are symmetric, so these are also the tiles that can step here
+            let position = self.position(index);
+            let moves = position.moves(self.width, self.height, |p| self.is_blocked(p));
+            for (from, diagonal) in moves {
+                let Some(from_index) = self.index(from) else {
+                    continue;
+                };
+                let route = cost + step_cost(diagonal, self.costs[index]);
+                if route < self.distances[from_index] {
+                    heap.push(Reverse((route, from_index)));
+                }
+            }
+        }
+    }
+}
+
+/// How an enemy finds its way to the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route<'a> {
+    /// Fixed waypoints, tracked by the enemy's `path_index`
+    Waypoints(&'a [(u8, u8)]),
+    /// Downhill on a flow field towards its goal
+    Flow(&'a FlowField),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,6 +639,124 @@ mod tests {
         assert!(would_block);
     }
 
+    #[test]
+    fn test_moves_do_not_cut_corners() {
+        let pos = Position::new(5, 5);
+        let open = pos.moves(10, 10, |_| false);
+        assert_eq!(open.len(), 8);
+        assert_eq!(open.iter().filter(|(_, diagonal)| *diagonal).count(), 4);
+
+        // A block to the right rules out both diagonals that would brush past it
+        let moves = pos.moves(10, 10, |p| p == Position::new(6, 5));
+        assert_eq!(moves.len(), 5);
+        assert!(!moves.iter().any(|(p, _)| *p == Position::new(6, 4)));
+        assert!(!moves.iter().any(|(p, _)| *p == Position::new(6, 6)));
+        assert!(moves.contains(&(Position::new(4, 4), true)));
+
+        assert_eq!(Position::new(0, 0).moves(10, 10, |_| false).len(), 3);
+    }
+
+    #[test]
+    fn test_octile_distance() {
+        let p1 = Position::new(0, 0);
+        assert_eq!(p1.octile_distance(&Position::new(3, 4)), 3 * 1414 + 1000);
+        assert_eq!(p1.octile_distance(&Position::new(5, 0)), 5000);
+    }
+
+    #[test]
+    fn test_find_path_diagonal() {
+        let path = find_path(
+            Position::new(0, 0),
+            Position::new(5, 5),
+            10,
+            10,
+            &HashSet::new(),
+        );
+        assert_eq!(path.unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_find_path_follows_cheap_terrain() {
+        let start = Position::new(0, 5);
+        let goal = Position::new(9, 5);
+
+        // Mud across the straight line, a road one row up
+        let mut terrain = TerrainMap::new();
+        for x in 1..9 {
+            terrain.insert((x, 5), Terrain::Mud);
+            terrain.insert((x, 4), Terrain::Road);
+        }
+
+        let path = find_path_on_terrain(start, goal, 10, 10, &HashSet::new(), &terrain).unwrap();
+        assert!(path[1..path.len() - 1].iter().all(|p| p.y == 4));
+    }
+
+    #[test]
+    fn test_flow_field_distances() {
+        let mut blocked = HashSet::new();
+        blocked.insert(Position::new(5, 5));
+        let field = FlowField::build(Position::new(9, 5), 10, 10, &blocked);
+
+        assert_eq!(field.distance(Position::new(9, 5)), Some(0));
+        assert_eq!(field.distance(Position::new(6, 5)), Some(3000));
+        assert_eq!(field.distance(Position::new(7, 7)), Some(2828)); // 2 diagonals
+        assert_eq!(field.distance(Position::new(5, 5)), None);
+        // Around the block: up, right, right, then a diagonal that no longer cuts its corner
+        assert_eq!(field.distance(Position::new(4, 5)), Some(6414));
+
+        let path = field.path_from(Position::new(0, 5)).unwrap();
+        assert!(!path.contains(&Position::new(5, 5)));
+        assert_eq!(path.last(), Some(&Position::new(9, 5)));
+        assert!(path
+            .windows(2)
+            .all(|step| field.distance(step[0]) > field.distance(step[1])));
+
+        // Between (4, 5) and the blocked (5, 5), head back to (4, 5)
+        assert_eq!(
+            field.next_tile_from((4500, 5000)),
+            Some(Position::new(4, 5))
+        );
+        assert_eq!(field.next_tile_from((9000, 5000)), None);
+    }
+
+    #[test]
+    fn test_flow_field_cut_off() {
+        let wall: HashSet<Position> = (0..10).map(|y| Position::new(5, y)).collect();
+        let field = FlowField::build(Position::new(9, 5), 10, 10, &wall);
+
+        assert_eq!(field.distance(Position::new(0, 0)), None);
+        assert!(field.path_from(Position::new(0, 0)).is_none());
+        assert!(field.next_tile_from((2000, 2000)).is_none());
+        assert_eq!(field.distance(Position::new(6, 0)), Some(6242));
+    }
+
+    #[test]
+    fn test_flow_field_incremental_matches_rebuild() {
+        let goal = Position::new(7, 3);
+        let mut rng = crate::rng::GameRng::new(17);
+        let mut terrain = TerrainMap::new();
+        for _ in 0..40 {
+            let tile = (rng.below(12) as u8, rng.below(12) as u8);
+            terrain.insert(tile, Terrain::ALL[rng.below(4) as usize]);
+        }
+        let mut blocked = HashSet::new();
+        let mut field = FlowField::build_on_terrain(goal, 12, 12, &blocked, &terrain);
+
+        for _ in 0..400 {
+            let tile = Position::new(rng.below(12) as u8, rng.below(12) as u8);
+            let block = rng.chance_permille(600);
+            if block {
+                blocked.insert(tile);
+            } else {
+                blocked.remove(&tile);
+            }
+
+            field.set_blocked(tile, block);
+            let rebuilt = FlowField::build_on_terrain(goal, 12, 12, &blocked, &terrain);
+            assert_eq!(field, rebuilt);
+        }
+    }
+
     #[test]
     fn test_complex_path() {
         let start = Position::new(0, 0);
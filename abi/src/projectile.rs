@@ -0,0 +1,111 @@
+use crate::fixed::{distance, MICROS_PER_SECOND};
+use crate::tower::Tower;
+use serde::{Deserialize, Serialize};
+
+/// Distance (milli-tiles) from a lobbed shell's landing point within which it still hits
+pub const LOB_HIT_RADIUS: u32 = 500;
+
+/// How a projectile travels to its target
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ProjectileMotion {
+    /// Follows the target and fizzles if the target dies first
+    Homing,
+    /// Flies to where the target stood when fired and explodes there
+    Lobbed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Projectile {
+    pub id: u64,
+    pub target_id: u64,
+    pub motion: ProjectileMotion,
+    /// Position in milli-tiles
+    pub position: (i32, i32),
+    /// Landing point of a lobbed shell, in milli-tiles
+    pub target_position: (i32, i32),
+    /// Speed in milli-tiles per second
+    pub speed: u32,
+    /// The firing tower as it was at launch; its stats and effects apply on impact
+    pub source: Tower,
+}
+
+impl Projectile {
+    /// Launch a shot from `tower` at an enemy standing at `target_position`.
+    /// Returns None for towers whose shots land instantly.
+    pub fn launch(
+        id: u64,
+        tower: &Tower,
+        target_id: u64,
+        target_position: (i32, i32),
+    ) -> Option<Self> {
+        let (motion, speed) = tower.tower_type.projectile()?;
+        Some(Self {
+            id,
+            target_id,
+            motion,
+            position: tower.center(),
+            target_position,
+            speed,
+            source: tower.clone(),
+        })
+    }
+
+    pub fn tower_id(&self) -> u64 {
+        self.source.id
+    }
+
+    /// Fly towards `destination` for `delta_micros`; returns true on arrival
+    pub fn advance(&mut self, destination: (i32, i32), delta_micros: u64) -> bool {
+        let step = (self.speed as u64).saturating_mul(delta_micros) / MICROS_PER_SECOND;
+        let remaining = distance(self.position, destination) as u64;
+
+        if remaining <= step {
+            self.position = destination;
+            return true;
+        }
+
+        // Truncating toward zero keeps this deterministic
+        let dx = (destination.0 - self.position.0) as i64;
+        let dy = (destination.1 - self.position.1) as i64;
+        self.position.0 += (dx * step as i64 / remaining as i64) as i32;
+        self.position.1 += (dy * step as i64 / remaining as i64) as i32;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tower::TowerType;
+
+    #[test]
+    fn test_launch_profiles() {
+        let arrow = Tower::new(0, (5, 5), TowerType::Arrow, 0);
+        let shot = Projectile::launch(0, &arrow, 3, (7000, 5000)).unwrap();
+        assert_eq!(shot.motion, ProjectileMotion::Homing);
+        assert_eq!(shot.position, (5000, 5000));
+        assert_eq!(shot.tower_id(), 0);
+
+        let cannon = Tower::new(1, (5, 5), TowerType::Cannon, 0);
+        let shell = Projectile::launch(1, &cannon, 3, (7000, 5000)).unwrap();
+        assert_eq!(shell.motion, ProjectileMotion::Lobbed);
+        assert!(shell.speed < shot.speed);
+
+        // Lightning strikes instantly
+        let lightning = Tower::new(2, (5, 5), TowerType::Lightning, 0);
+        assert!(Projectile::launch(2, &lightning, 3, (7000, 5000)).is_none());
+    }
+
+    #[test]
+    fn test_advance() {
+        let cannon = Tower::new(0, (0, 0), TowerType::Cannon, 0);
+        let mut shell = Projectile::launch(0, &cannon, 0, (3000, 4000)).unwrap();
+        shell.speed = 2000;
+
+        assert!(!shell.advance((3000, 4000), 1_000_000));
+        assert_eq!(shell.position, (1200, 1600));
+        assert!(!shell.advance((3000, 4000), 1_000_000));
+        assert!(shell.advance((3000, 4000), 1_000_000));
+        assert_eq!(shell.position, (3000, 4000));
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// SplitMix64 generator: tiny state, fast, and good enough for gameplay rolls
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Derive a seed from arbitrary bytes (e.g. a game ID) with FNV-1a
+    pub fn seed_from_bytes(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (0 if `bound` is 0)
+    pub fn below(&mut self, bound: u32) -> u32 {
+        if bound == 0 {
+            return 0;
+        }
+        // Multiply-shift keeps the result unbiased enough without a retry loop
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as u32
+    }
+
+    /// Roll a `permille` in 1000 chance. Never draws for 0 or certain odds.
+    pub fn chance_permille(&mut self, permille: u32) -> bool {
+        match permille {
+            0 => false,
+            1000.. => true,
+            _ => self.below(1000) < permille,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+        let mut c = GameRng::new(43);
+
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let second: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        let other: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_below_and_chance() {
+        let mut rng = GameRng::new(7);
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+        assert_eq!(rng.below(0), 0);
+
+        // 0 and certain odds never consume a draw
+        let before = rng;
+        assert!(!rng.chance_permille(0));
+        assert!(rng.chance_permille(1000));
+        assert_eq!(rng, before);
+
+        let hits = (0..10_000).filter(|_| rng.chance_permille(250)).count();
+        assert!((2_200..2_800).contains(&hits));
+    }
+
+    #[test]
+    fn test_seed_from_bytes() {
+        let seed = GameRng::seed_from_bytes(b"game_1");
+        assert_eq!(seed, GameRng::seed_from_bytes(b"game_1"));
+        assert_ne!(seed, GameRng::seed_from_bytes(b"game_2"));
+    }
+}
@@ -0,0 +1,1326 @@
+use crate::aura::apply_auras;
+use crate::combat::*;
+use crate::economy::{calculate_wave_bonus, EconomyConfig};
+use crate::endless::EndlessScaling;
+use crate::enemy::{Enemy, EnemyAbility, MovementClass, PhaseEffect};
+use crate::fixed::{apply_permille, distance_squared, within_radius};
+use crate::game::{GameConfig, Grid};
+use crate::mutator::{apply_enemy_mutators, apply_tower_mutators, Mutator};
+use crate::pathfinding::Route;
+use crate::projectile::{Projectile, ProjectileMotion, LOB_HIT_RADIUS};
+use crate::rng::GameRng;
+use crate::status::{StatusEffect, StatusKind};
+use crate::tower::{Tower, CRIT_DAMAGE_PERMILLE};
+use crate::wave::{self, ScheduledSpawn};
+use serde::{Deserialize, Serialize};
+
+/// Something that happened during a simulation step, in the order it happened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimEvent {
+    EnemySpawned {
+        enemy_id: u64,
+        lane: u8,
+    },
+    TowerFired {
+        tower_id: u64,
+        target_id: u64,
+        damage: u32,
+    },
+    /// An enemy took chain, splash or damage-over-time damage from a tower
+    EnemyDamaged {
+        enemy_id: u64,
+        tower_id: u64,
+        damage: u32,
+    },
+    EnemyKilled {
+        enemy_id: u64,
+        tower_id: u64,
+        gold_reward: u64,
+    },
+    EnemyReachedBase {
+        enemy_id: u64,
+        damage: u32,
+        remaining_health: u32,
+    },
+    /// A healer or regenerator restored health
+    EnemyHealed {
+        enemy_id: u64,
+        amount: u32,
+    },
+    /// A splitter died and left a child behind
+    EnemySplit {
+        parent_id: u64,
+        child_id: u64,
+    },
+    /// A boss dropped below a health threshold and entered a new phase
+    BossPhaseChanged {
+        enemy_id: u64,
+        phase: u8,
+        effects: Vec<PhaseEffect>,
+    },
+    /// A boss summoned a minion where it stands
+    MinionSummoned {
+        boss_id: u64,
+        minion_id: u64,
+    },
+    /// A boss switched a tower off
+    TowerDisabled {
+        tower_id: u64,
+        until_micros: u64,
+    },
+    /// A tower launched a projectile; damage is dealt when it lands
+    ProjectileLaunched {
+        projectile_id: u64,
+        tower_id: u64,
+        target_id: u64,
+    },
+    /// A projectile landed without hitting anything
+    ProjectileMissed {
+        projectile_id: u64,
+        tower_id: u64,
+    },
+    /// A tower shot rolled a critical hit
+    CriticalHit {
+        tower_id: u64,
+        target_id: u64,
+    },
+    /// An enemy dodged a tower hit
+    EnemyDodged {
+        enemy_id: u64,
+        tower_id: u64,
+    },
+    /// A gold mine paid out
+    GoldGenerated {
+        tower_id: u64,
+        amount: u64,
+    },
+    BaseDestroyed,
+    WaveCleared {
+        wave_number: u32,
+        bonus_gold: u64,
+    },
+}
+
+/// Pure, deterministic game simulation.
+///
+/// Owns everything the tick loop needs so a game can be played without a
+/// Linera runtime. The contract loads this from its views, calls `step` and
+/// persists the result; tests, replays and bots drive it directly.
+/// Towers and enemies are kept sorted by ID so iteration order is stable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    pub config: GameConfig,
+    pub grid: Grid,
+    pub towers: Vec<Tower>,
+    pub enemies: Vec<Enemy>,
+    pub gold: u64,
+    pub base_health: u32,
+    pub wave_number: u32,
+    pub wave_active: bool,
+    pub time_micros: u64,
+    /// Enemies of the current wave that have not entered the map yet, in spawn order
+    pub pending_spawns: Vec<ScheduledSpawn>,
+    /// Time since the current wave started
+    pub wave_elapsed_micros: u64,
+    pub next_enemy_id: u64,
+    /// Shots in flight, sorted by ID (only used when `config.projectiles` is set)
+    pub projectiles: Vec<Projectile>,
+    pub next_projectile_id: u64,
+    /// Source of every random roll (crits, dodges, wave variance)
+    pub rng: GameRng,
+}
+
+impl Simulation {
+    pub fn new(grid: Grid, gold: u64, base_health: u32) -> Self {
+        Self {
+            config: GameConfig::default(),
+            grid,
+            towers: Vec::new(),
+            enemies: Vec::new(),
+            gold,
+            base_health,
+            wave_number: 0,
+            wave_active: false,
+            time_micros: 0,
+            pending_spawns: Vec::new(),
+            wave_elapsed_micros: 0,
+            next_enemy_id: 0,
+            projectiles: Vec::new(),
+            next_projectile_id: 0,
+            rng: GameRng::default(),
+        }
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.base_health == 0
+    }
+
+    /// Add a tower, keeping the tower list sorted by ID
+    pub fn add_tower(&mut self, tower: Tower) {
+        let index = self.towers.partition_point(|t| t.id < tower.id);
+        self.towers.insert(index, tower);
+    }
+
+    /// Add an enemy, keeping the enemy list sorted by ID
+    pub fn add_enemy(&mut self, enemy: Enemy) {
+        let index = self.enemies.partition_point(|e| e.id < enemy.id);
+        self.enemies.insert(index, enemy);
+    }
+
+    /// Block or free a tile of the mazing flow field when a tower is placed or
+    /// sold. Enemies already on the field follow the updated distances; a block
+    /// that would cut off the base or trap one of them is undone.
+    pub fn set_tile_blocked(&mut self, tile: (u8, u8), blocked: bool) -> Result<(), &'static str> {
+        self.grid.set_blocked(tile, blocked)?;
+
+        let Route::Flow(field) = self.grid.route(MovementClass::Ground, 0) else {
+            return Ok(());
+        };
+        let trapped = self
+            .enemies
+            .iter()
+            .filter(|e| e.movement == MovementClass::Ground)
+            .any(|e| field.next_tile_from(e.position).is_none());
+        if trapped {
+            self.grid.set_blocked(tile, !blocked)?;
+            return Err("Placement would trap an enemy");
+        }
+        Ok(())
+    }
+
+    /// Advance to the next wave and queue its spawn schedule.
+    /// Returns the number of enemies in the wave.
+    pub fn start_wave(&mut self) -> usize {
+        self.wave_number = self.wave_number.saturating_add(1);
+        self.wave_active = true;
+        self.wave_elapsed_micros = 0;
+
+        let spawn_points = self.grid.spawn_points();
+        let mut groups = match &self.config.wave_script {
+            Some(script) => script.groups(self.wave_number),
+            None => {
+                let mut groups =
+                    wave::wave_groups(self.wave_number, &self.config.wave_config, &mut self.rng);
+                // Procedural waves take turns between the map's lanes
+                for (index, group) in groups.iter_mut().enumerate() {
+                    group.lane = (index % spawn_points.len().max(1)) as u8;
+                }
+                groups
+            }
+        };
+        let endless = self
+            .config
+            .endless
+            .then(|| EndlessScaling::for_wave(self.wave_number));
+        if let Some(scaling) = &endless {
+            groups
+                .iter_mut()
+                .for_each(|group| scaling.scale_group(group));
+        }
+        let mut schedule = wave::schedule_groups(
+            &groups,
+            self.wave_number,
+            &spawn_points,
+            self.config.difficulty,
+        );
+        let mut mutators = self.active_mutators();
+        if let Some(scaling) = &endless {
+            // The curve applies the upgrades it has unlocked itself
+            mutators.retain(|m| m.endless().is_none_or(|e| !scaling.mutators.contains(&e)));
+        }
+        for spawn in schedule.iter_mut() {
+            if let Some(scaling) = &endless {
+                scaling.apply(&mut spawn.enemy);
+            }
+            apply_enemy_mutators(&mut spawn.enemy, &mutators);
+            spawn.enemy.id = self.next_enemy_id;
+            self.next_enemy_id = self.next_enemy_id.saturating_add(1);
+        }
+
+        let enemy_count = schedule.len();
+        self.pending_spawns = schedule;
+        enemy_count
+    }
+
+    /// Mutators in force for the current wave
+    pub fn active_mutators(&self) -> Vec<Mutator> {
+        self.config.mutators_for_wave(self.wave_number)
+    }
+
+    /// Advance the simulation by `delta_micros` and return what happened
+    pub fn step(&mut self, delta_micros: u64) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.is_defeated() {
+            return events;
+        }
+
+        self.time_micros = self.time_micros.saturating_add(delta_micros);
+
+        self.move_enemies(delta_micros, &mut events);
+        if self.is_defeated() {
+            events.push(SimEvent::BaseDestroyed);
+            return events;
+        }
+
+        self.tick_status_effects(delta_micros, &mut events);
+        self.resolve_abilities(delta_micros, &mut events);
+
+        if self.wave_active {
+            self.wave_elapsed_micros = self.wave_elapsed_micros.saturating_add(delta_micros);
+            self.release_spawns(&mut events);
+        }
+
+        apply_auras(&mut self.towers);
+        let mutators = self.active_mutators();
+        apply_tower_mutators(&mut self.towers, &mutators);
+        self.move_projectiles(delta_micros, &mut events);
+        self.fire_towers(&mut events);
+        self.resolve_boss_phases(&mut events);
+
+        if self.wave_active && self.enemies.is_empty() && self.pending_spawns.is_empty() {
+            self.wave_active = false;
+            self.projectiles.clear();
+            let bonus_gold = calculate_wave_bonus(self.wave_number, &EconomyConfig::default());
+            self.gold = self.gold.saturating_add(bonus_gold);
+            events.push(SimEvent::WaveCleared {
+                wave_number: self.wave_number,
+                bonus_gold,
+            });
+        }
+
+        events
+    }
+
+    /// Move every scheduled enemy whose delay has elapsed onto the map
+    fn release_spawns(&mut self, events: &mut Vec<SimEvent>) {
+        let due = self
+            .pending_spawns
+            .partition_point(|spawn| spawn.delay_micros <= self.wave_elapsed_micros);
+
+        for spawn in self.pending_spawns.drain(..due).collect::<Vec<_>>() {
+            events.push(SimEvent::EnemySpawned {
+                enemy_id: spawn.enemy.id,
+                lane: spawn.lane,
+            });
+            self.add_enemy(spawn.enemy);
+        }
+    }
+
+    fn move_enemies(&mut self, delta_micros: u64, events: &mut Vec<SimEvent>) {
+        let mut index = 0;
+
+        while index < self.enemies.len() {
+            let enemy = &mut self.enemies[index];
+            let route = self.grid.route(enemy.movement, enemy.lane);
+
+            if !update_enemy_position(enemy, route, &self.grid.terrain, delta_micros) {
+                index += 1;
+                continue;
+            }
+
+            // Reached the base
+            let enemy = self.enemies.remove(index);
+            self.base_health = self.base_health.saturating_sub(enemy.damage_to_base);
+            events.push(SimEvent::EnemyReachedBase {
+                enemy_id: enemy.id,
+                damage: enemy.damage_to_base,
+                remaining_health: self.base_health,
+            });
+
+            if self.is_defeated() {
+                return;
+            }
+        }
+    }
+
+    fn tick_status_effects(&mut self, delta_micros: u64, events: &mut Vec<SimEvent>) {
+        let mut index = 0;
+
+        while index < self.enemies.len() {
+            let enemy = &mut self.enemies[index];
+            let mut killed_by = None;
+
+            for (tower_id, damage) in enemy.tick_status_effects(delta_micros) {
+                if !enemy.is_alive() {
+                    break;
+                }
+                if enemy.take_damage(damage) {
+                    killed_by = Some(tower_id);
+                }
+                if let Some(tower) = self.towers.iter_mut().find(|t| t.id == tower_id) {
+                    tower.record_damage(damage as u64);
+                }
+                events.push(SimEvent::EnemyDamaged {
+                    enemy_id: enemy.id,
+                    tower_id,
+                    damage,
+                });
+            }
+
+            match killed_by {
+                Some(tower_id) => self.kill_enemy(index, tower_id, events),
+                None => index += 1,
+            }
+        }
+    }
+
+    /// Resolve healer and regenerator abilities
+    fn resolve_abilities(&mut self, delta_micros: u64, events: &mut Vec<SimEvent>) {
+        for index in 0..self.enemies.len() {
+            let ticks = self.enemies[index].advance_ability(delta_micros);
+            if ticks == 0 {
+                continue;
+            }
+            let ticks = ticks.min(u32::MAX as u64) as u32;
+
+            match self.enemies[index].ability {
+                Some(EnemyAbility::Heal { amount, radius, .. }) => {
+                    let healer_id = self.enemies[index].id;
+                    let center = self.enemies[index].position;
+                    for ally in self.enemies.iter_mut() {
+                        if ally.id == healer_id || !within_radius(ally.position, center, radius) {
+                            continue;
+                        }
+                        let healed = ally.heal(amount.saturating_mul(ticks));
+                        if healed > 0 {
+                            events.push(SimEvent::EnemyHealed {
+                                enemy_id: ally.id,
+                                amount: healed,
+                            });
+                        }
+                    }
+                }
+                Some(EnemyAbility::Regenerate { per_second }) => {
+                    let enemy = &mut self.enemies[index];
+                    let healed = enemy.heal(per_second.saturating_mul(ticks));
+                    if healed > 0 {
+                        events.push(SimEvent::EnemyHealed {
+                            enemy_id: enemy.id,
+                            amount: healed,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Remove a killed enemy, pay its bounty and leave any splitter children behind
+    fn kill_enemy(&mut self, index: usize, tower_id: u64, events: &mut Vec<SimEvent>) {
+        let enemy = self.enemies.remove(index);
+        self.gold = self.gold.saturating_add(enemy.gold_reward);
+        events.push(SimEvent::EnemyKilled {
+            enemy_id: enemy.id,
+            tower_id,
+            gold_reward: enemy.gold_reward,
+        });
+
+        let children = enemy.split_children(self.next_enemy_id, self.wave_number);
+        self.next_enemy_id = self.next_enemy_id.saturating_add(children.len() as u64);
+        for child in children {
+            events.push(SimEvent::EnemySplit {
+                parent_id: enemy.id,
+                child_id: child.id,
+            });
+            self.add_enemy(child);
+        }
+    }
+
+    /// Enter any boss phases whose health threshold was crossed this step
+    fn resolve_boss_phases(&mut self, events: &mut Vec<SimEvent>) {
+        for index in 0..self.enemies.len() {
+            while let Some((phase, definition)) = self.enemies[index].advance_boss_phase() {
+                let boss_id = self.enemies[index].id;
+                events.push(SimEvent::BossPhaseChanged {
+                    enemy_id: boss_id,
+                    phase,
+                    effects: definition.effects.to_vec(),
+                });
+                for effect in definition.effects {
+                    self.apply_phase_effect(boss_id, *effect, events);
+                }
+            }
+        }
+    }
+
+    fn apply_phase_effect(
+        &mut self,
+        boss_id: u64,
+        effect: PhaseEffect,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let boss = match self.enemies.iter_mut().find(|e| e.id == boss_id) {
+            Some(boss) => boss,
+            None => return,
+        };
+
+        match effect {
+            PhaseEffect::ChangeSpeed { speed_permille } => {
+                boss.speed = apply_permille(boss.speed, speed_permille);
+            }
+            PhaseEffect::Immunity { duration_micros } => {
+                // Self-inflicted, so there is no tower to credit
+                boss.apply_status(StatusEffect::new(StatusKind::Immune, 0, duration_micros, 0));
+            }
+            PhaseEffect::SpawnMinions { minion, count } => {
+                let minions = boss.spawn_here(self.next_enemy_id, minion, count, self.wave_number);
+                self.next_enemy_id = self.next_enemy_id.saturating_add(minions.len() as u64);
+                for minion in minions {
+                    events.push(SimEvent::MinionSummoned {
+                        boss_id,
+                        minion_id: minion.id,
+                    });
+                    self.add_enemy(minion);
+                }
+            }
+            PhaseEffect::DisableTowers {
+                radius,
+                duration_micros,
+            } => {
+                let center = boss.position;
+                let until_micros = self.time_micros.saturating_add(duration_micros);
+                for tower in self.towers.iter_mut() {
+                    if !within_radius(tower.center(), center, radius) {
+                        continue;
+                    }
+                    tower.disable_until(until_micros);
+                    events.push(SimEvent::TowerDisabled {
+                        tower_id: tower.id,
+                        until_micros: tower.disabled_until_micros,
+                    });
+                }
+            }
+        }
+    }
+
+    fn fire_towers(&mut self, events: &mut Vec<SimEvent>) {
+        for tower_index in 0..self.towers.len() {
+            let tower = &mut self.towers[tower_index];
+            if !tower.can_fire(self.time_micros) {
+                continue;
+            }
+
+            if tower.tower_type.is_support() {
+                // Gold mines pay out on their cycle; aura towers never fire
+                if tower.stats.gold_income > 0 {
+                    tower.last_shot_micros = self.time_micros;
+                    self.gold = self.gold.saturating_add(tower.stats.gold_income);
+                    events.push(SimEvent::GoldGenerated {
+                        tower_id: tower.id,
+                        amount: tower.stats.gold_income,
+                    });
+                }
+                continue;
+            }
+
+            let target = match find_target(tower, &self.enemies, &self.grid) {
+                Some(target) => target,
+                None => continue,
+            };
+            tower.last_shot_micros = self.time_micros;
+
+            if self.config.projectiles {
+                let projectile_id = self.next_projectile_id;
+                if let Some(projectile) =
+                    Projectile::launch(projectile_id, tower, target.id, target.position)
+                {
+                    self.next_projectile_id = self.next_projectile_id.saturating_add(1);
+                    events.push(SimEvent::ProjectileLaunched {
+                        projectile_id,
+                        tower_id: tower.id,
+                        target_id: target.id,
+                    });
+                    self.projectiles.push(projectile);
+                    continue;
+                }
+            }
+
+            let target_id = target.id;
+            let hits = resolve_hits(tower, target, &self.enemies);
+            let tower = tower.clone();
+            self.apply_hits(&tower, target_id, hits, events);
+        }
+    }
+
+    /// Fly every projectile and resolve the ones that land
+    fn move_projectiles(&mut self, delta_micros: u64, events: &mut Vec<SimEvent>) {
+        for mut projectile in std::mem::take(&mut self.projectiles) {
+            let target = self.enemies.iter().find(|e| e.id == projectile.target_id);
+            let destination = match (projectile.motion, target) {
+                (ProjectileMotion::Homing, Some(target)) => target.position,
+                (ProjectileMotion::Homing, None) => {
+                    // The target died before the shot landed
+                    events.push(SimEvent::ProjectileMissed {
+                        projectile_id: projectile.id,
+                        tower_id: projectile.tower_id(),
+                    });
+                    continue;
+                }
+                (ProjectileMotion::Lobbed, _) => projectile.target_position,
+            };
+
+            if !projectile.advance(destination, delta_micros) {
+                self.projectiles.push(projectile);
+                continue;
+            }
+
+            // Homing shots strike their target; shells hit whoever is closest to the landing point
+            let tower = &projectile.source;
+            let struck = match projectile.motion {
+                ProjectileMotion::Homing => target,
+                ProjectileMotion::Lobbed => self
+                    .enemies
+                    .iter()
+                    .filter(|e| e.is_alive() && tower.tower_type.can_target(e.movement))
+                    .filter(|e| within_radius(e.position, projectile.position, LOB_HIT_RADIUS))
+                    .min_by_key(|e| (distance_squared(e.position, projectile.position), e.id)),
+            };
+
+            match struck {
+                Some(enemy) => {
+                    let (target_id, hits) = (enemy.id, resolve_hits(tower, enemy, &self.enemies));
+                    self.apply_hits(tower, target_id, hits, events);
+                }
+                None => events.push(SimEvent::ProjectileMissed {
+                    projectile_id: projectile.id,
+                    tower_id: projectile.tower_id(),
+                }),
+            }
+        }
+    }
+
+    /// Apply one shot's hits, crediting `tower` for damage and kills
+    fn apply_hits(
+        &mut self,
+        tower: &Tower,
+        target_id: u64,
+        hits: Vec<Hit>,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let tower_id = tower.id;
+
+        // One crit roll per shot doubles every hit it lands
+        let critical = self.rng.chance_permille(tower.stats.crit_chance_permille);
+        if critical {
+            events.push(SimEvent::CriticalHit {
+                tower_id,
+                target_id,
+            });
+        }
+
+        for mut hit in hits {
+            let index = match self.enemies.iter().position(|e| e.id == hit.enemy_id) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let enemy = &mut self.enemies[index];
+            if self.rng.chance_permille(enemy.dodge_chance_permille) {
+                events.push(SimEvent::EnemyDodged {
+                    enemy_id: hit.enemy_id,
+                    tower_id,
+                });
+                continue;
+            }
+            if critical {
+                hit.damage = apply_permille(hit.damage, CRIT_DAMAGE_PERMILLE);
+            }
+
+            apply_tower_effects(tower, enemy);
+            let killed = enemy.take_damage(hit.damage);
+            // The tower may have been sold while its shot was in flight
+            if let Some(source) = self.towers.iter_mut().find(|t| t.id == tower_id) {
+                source.record_damage(hit.damage as u64);
+            }
+
+            if hit.enemy_id == target_id {
+                events.push(SimEvent::TowerFired {
+                    tower_id,
+                    target_id,
+                    damage: hit.damage,
+                });
+            } else {
+                events.push(SimEvent::EnemyDamaged {
+                    enemy_id: hit.enemy_id,
+                    tower_id,
+                    damage: hit.damage,
+                });
+            }
+
+            if killed {
+                self.kill_enemy(index, tower_id, events);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endless::ARMORED_BONUS;
+    use crate::enemy::EnemyType;
+    use crate::fixed::{milli_pos_to_tile, tile_pos_to_milli};
+    use crate::map::MapDefinition;
+    use crate::pathfinding::Position;
+    use crate::status::{StatusEffect, StatusKind};
+    use crate::tower::TowerType;
+    use crate::wave::WaveScript;
+
+    fn sim_with_enemy(enemy_type: EnemyType) -> Simulation {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        let spawn = sim.grid.lane(0).spawn_point;
+        sim.add_enemy(Enemy::new(0, enemy_type, 1, spawn));
+        sim.wave_active = true;
+        sim
+    }
+
+    #[test]
+    fn test_start_wave_queues_spawns() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        let count = sim.start_wave();
+
+        assert_eq!(sim.wave_number, 1);
+        assert!(sim.wave_active);
+        assert!(sim.enemies.is_empty());
+        assert_eq!(sim.pending_spawns.len(), count);
+        assert_eq!(sim.next_enemy_id, count as u64);
+    }
+
+    #[test]
+    fn test_spawns_are_released_over_time() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        let count = sim.start_wave();
+
+        let events = sim.step(100_000);
+        let spawned = events
+            .iter()
+            .filter(|e| matches!(e, SimEvent::EnemySpawned { .. }))
+            .count();
+        assert!(spawned > 0 && spawned < count);
+        assert_eq!(sim.enemies.len() + sim.pending_spawns.len(), count);
+
+        // Released enemies start at the spawn point, not stacked mid-path
+        let spawn = tile_pos_to_milli(sim.grid.lane(0).spawn_point);
+        assert!(sim.enemies.iter().all(|e| e.position == spawn));
+
+        for _ in 0..600 {
+            sim.step(100_000);
+        }
+        assert!(sim.pending_spawns.is_empty());
+    }
+
+    #[test]
+    fn test_enemy_ids_continue_across_waves() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        let first = sim.start_wave() as u64;
+        sim.pending_spawns.clear();
+        sim.start_wave();
+
+        assert_eq!(sim.pending_spawns[0].enemy.id, first);
+    }
+
+    #[test]
+    fn test_start_wave_uses_wave_script() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        sim.config.wave_script = Some(
+            WaveScript::from_json(
+                r#"{"name": "Duo", "waves": [{"groups": [{"enemy_type": "Tank", "count": 2}]}]}"#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(sim.start_wave(), 2);
+        assert!(sim
+            .pending_spawns
+            .iter()
+            .all(|s| s.enemy.enemy_type == EnemyType::Tank));
+        assert_eq!(sim.config.final_wave(), Some(1));
+    }
+
+    #[test]
+    fn test_waves_target_lanes() {
+        let map = MapDefinition::from_json(include_str!("../maps/crossroads.json")).unwrap();
+        let mut sim = Simulation::new(map.to_grid(), 500, 20);
+        sim.config.wave_script = Some(
+            WaveScript::from_json(
+                r#"{"name": "Flank", "waves": [{"groups": [
+                    {"enemy_type": "Tank", "count": 1, "lane": 1},
+                    {"enemy_type": "Tank", "count": 1, "lane": 5}
+                ]}]}"#,
+            )
+            .unwrap(),
+        );
+        sim.start_wave();
+
+        // Lanes the map doesn't have fall back to lane 0
+        let lanes: Vec<u8> = sim.pending_spawns.iter().map(|s| s.enemy.lane).collect();
+        assert_eq!(lanes, vec![1, 0]);
+        let events = sim.step(100_000);
+        assert!(events.contains(&SimEvent::EnemySpawned {
+            enemy_id: 0,
+            lane: 1
+        }));
+        assert_eq!(sim.enemies[0].position, tile_pos_to_milli((0, 9)));
+        assert_eq!(sim.enemies[1].position, tile_pos_to_milli((0, 2)));
+
+        // The flanking tank stays south of lane 0 all the way to the base
+        for _ in 0..100 {
+            sim.step(500_000);
+            if let Some(tank) = sim.enemies.iter().find(|e| e.id == 0) {
+                assert!(tank.position.1 >= 6_000);
+            }
+        }
+        assert!(sim.enemies.is_empty());
+
+        // Procedural waves take turns between the lanes
+        sim.config.wave_script = None;
+        sim.start_wave();
+        assert!(sim.pending_spawns.iter().any(|s| s.lane == 0));
+        assert!(sim.pending_spawns.iter().any(|s| s.lane == 1));
+    }
+
+    #[test]
+    fn test_mutators_apply_in_simulation() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        sim.config.mutators = vec![Mutator::Fog];
+        sim.config.wave_script = Some(
+            WaveScript::from_json(
+                r#"{"name": "Gale", "waves": [
+                    {"groups": [{"enemy_type": "BasicScout", "count": 1}]},
+                    {"groups": [{"enemy_type": "BasicScout", "count": 1}], "mutators": ["Swift"]}
+                ]}"#,
+            )
+            .unwrap(),
+        );
+        sim.add_tower(Tower::new(0, (5, 5), TowerType::Arrow, 0));
+
+        sim.start_wave();
+        assert_eq!(sim.pending_spawns[0].enemy.speed, 1000);
+        sim.step(1_000);
+        assert_eq!(
+            sim.towers[0].stats.range,
+            sim.towers[0].base_stats.range - 1
+        );
+
+        sim.wave_number = 1;
+        sim.start_wave();
+        assert_eq!(sim.active_mutators(), vec![Mutator::Fog, Mutator::Swift]);
+        assert_eq!(sim.pending_spawns[0].enemy.speed, 1300);
+    }
+
+    #[test]
+    fn test_endless_waves_escalate() {
+        let mut normal = Simulation::new(Grid::new(), 500, 20);
+        normal.config.wave_config.variance_percent = 0;
+        normal.wave_number = 120;
+        let mut endless = normal.clone();
+        endless.config.endless = true;
+
+        let normal_count = normal.start_wave();
+        let endless_count = endless.start_wave();
+        assert!(endless_count > normal_count);
+        let first = &endless.pending_spawns[0].enemy;
+        assert_eq!(
+            first.armor,
+            normal.pending_spawns[0].enemy.armor + ARMORED_BONUS
+        );
+        assert!(first.shield > 0);
+        assert!(first.max_health > normal.pending_spawns[0].enemy.max_health);
+
+        // A game mutator the curve has also unlocked still applies once
+        let mut armored = endless.clone();
+        armored.config.mutators = vec![Mutator::Armored];
+        armored.start_wave();
+        assert_eq!(armored.pending_spawns[0].enemy.armor, first.armor);
+    }
+
+    #[test]
+    fn test_wave_not_cleared_while_spawns_pending() {
+        let mut sim = Simulation::new(Grid::new(), 500, 20);
+        sim.start_wave();
+        sim.pending_spawns.retain(|s| s.delay_micros > 0);
+
+        let events = sim.step(100_000);
+
+        assert!(events.is_empty());
+        assert!(sim.wave_active);
+    }
+
+    #[test]
+    fn test_step_moves_enemies() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        let start = sim.enemies[0].position;
+
+        let events = sim.step(1_000_000);
+
+        assert!(events.is_empty());
+        assert_ne!(sim.enemies[0].position, start);
+        assert_eq!(sim.time_micros, 1_000_000);
+    }
+
+    #[test]
+    fn test_flow_field_walks_enemies_around_towers() {
+        let mut sim = Simulation::new(Grid::open_field(), 500, 20);
+        sim.config.mazing = true;
+        let spawn = sim.grid.lane(0).spawn_point;
+        sim.add_enemy(Enemy::new(0, EnemyType::BasicScout, 1, spawn));
+        sim.add_enemy(Enemy::new(1, EnemyType::Flyer, 1, spawn));
+        sim.wave_active = true;
+        sim.step(2_000_000);
+
+        // Wall off the straight route with a tower in front of the ground enemy
+        let ahead = sim.grid.lane(0).path[sim.enemies[0].path_index + 1];
+        let flyer_index = sim.enemies[1].path_index;
+        sim.add_tower(Tower::new(0, ahead, TowerType::Banner, 0));
+        sim.set_tile_blocked(ahead, true).unwrap();
+
+        assert!(!sim.grid.lane(0).path.contains(&ahead));
+        assert_eq!(sim.grid.lane(0).path.last(), Some(&sim.grid.base_point));
+        assert_eq!(sim.enemies[1].path_index, flyer_index);
+
+        // A banner never fires, so the scout walks the whole detour to the base
+        for _ in 0..100 {
+            sim.step(500_000);
+            if let Some(scout) = sim.enemies.iter().find(|e| e.id == 0) {
+                assert_ne!(milli_pos_to_tile(scout.position), ahead);
+            }
+        }
+        assert!(sim.enemies.is_empty());
+    }
+
+    #[test]
+    fn test_blocks_that_trap_enemies_are_undone() {
+        let mut sim = Simulation::new(Grid::open_field(), 500, 20);
+        sim.add_enemy(Enemy::new(0, EnemyType::BasicScout, 1, (5, 5)));
+        let field = sim.grid.flow_field.clone();
+
+        assert_eq!(
+            sim.set_tile_blocked((5, 5), true),
+            Err("Placement would trap an enemy")
+        );
+        assert_eq!(sim.grid.flow_field, field);
+        assert!(sim.set_tile_blocked((5, 6), true).is_ok());
+    }
+
+    #[test]
+    fn test_large_wave_through_a_maze() {
+        let mut sim = Simulation::new(Grid::open_field(), 500, 1_000);
+        // Two staggered walls force a long zig-zag
+        for y in 0..18 {
+            sim.set_tile_blocked((6, y), true).unwrap();
+            sim.set_tile_blocked((13, 19 - y), true).unwrap();
+        }
+        let maze_length = sim.grid.lane(0).path.len();
+        assert!(maze_length > 30);
+
+        let spawn = sim.grid.lane(0).spawn_point;
+        for id in 0..150 {
+            sim.add_enemy(Enemy::new(id, EnemyType::BasicScout, 1, spawn));
+        }
+        let damage = sim.enemies[0].damage_to_base;
+        sim.wave_active = true;
+
+        let field = sim.grid.flow_field.clone().unwrap();
+        for _ in 0..200 {
+            sim.step(500_000);
+            for enemy in &sim.enemies {
+                let tile = Position::from_tuple(milli_pos_to_tile(enemy.position));
+                assert!(!field.is_blocked(tile));
+            }
+        }
+
+        assert!(sim.enemies.is_empty());
+        assert_eq!(sim.base_health, 1_000 - 150 * damage);
+    }
+
+    #[test]
+    fn test_tower_kills_enemy_and_awards_gold() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.enemies[0].health = 5;
+        sim.add_tower(Tower::new(7, (1, 9), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+
+        assert!(events.contains(&SimEvent::TowerFired {
+            tower_id: 7,
+            target_id: 0,
+            damage: 10,
+        }));
+        assert!(events.contains(&SimEvent::EnemyKilled {
+            enemy_id: 0,
+            tower_id: 7,
+            gold_reward: 10,
+        }));
+        assert!(sim.enemies.is_empty());
+        assert_eq!(sim.towers[0].total_damage_dealt, 10);
+    }
+
+    #[test]
+    fn test_critical_hits_and_dodges() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        let mut tower = Tower::new(7, (1, 9), TowerType::Arrow, 0);
+        tower.base_stats.crit_chance_permille = 1000;
+        sim.add_tower(tower);
+
+        let events = sim.step(500_000);
+        assert!(events.contains(&SimEvent::CriticalHit {
+            tower_id: 7,
+            target_id: 0,
+        }));
+        assert!(events.contains(&SimEvent::TowerFired {
+            tower_id: 7,
+            target_id: 0,
+            damage: 20,
+        }));
+
+        let mut sim = sim_with_enemy(EnemyType::FastRunner);
+        sim.enemies[0].dodge_chance_permille = 1000;
+        sim.add_tower(Tower::new(7, (1, 9), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+        assert!(events.contains(&SimEvent::EnemyDodged {
+            enemy_id: 0,
+            tower_id: 7,
+        }));
+        assert_eq!(sim.enemies[0].health, sim.enemies[0].max_health);
+    }
+
+    #[test]
+    fn test_seed_reproduces_a_match() {
+        let play = |seed| {
+            let mut sim = Simulation::new(Grid::new(), 500, 20);
+            sim.rng = GameRng::new(seed);
+            sim.add_tower(Tower::new(0, (6, 9), TowerType::Arrow, 0));
+            sim.start_wave();
+            (0..300).flat_map(|_| sim.step(100_000)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(play(11), play(11));
+        assert_ne!(play(11), play(12));
+    }
+
+    #[test]
+    fn test_projectile_travels_before_hitting() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.config.projectiles = true;
+        sim.enemies[0].health = 5;
+        sim.add_tower(Tower::new(7, (1, 9), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+        assert!(events.contains(&SimEvent::ProjectileLaunched {
+            projectile_id: 0,
+            tower_id: 7,
+            target_id: 0,
+        }));
+        assert_eq!(sim.enemies[0].health, 5);
+        assert_eq!(sim.projectiles.len(), 1);
+
+        let events = sim.step(100_000);
+        assert!(events.contains(&SimEvent::EnemyKilled {
+            enemy_id: 0,
+            tower_id: 7,
+            gold_reward: 10,
+        }));
+        assert!(sim.projectiles.is_empty());
+        assert_eq!(sim.towers[0].total_damage_dealt, 10);
+    }
+
+    #[test]
+    fn test_homing_shot_fizzles_when_target_dies() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.config.projectiles = true;
+        sim.add_tower(Tower::new(7, (1, 9), TowerType::Arrow, 0));
+        sim.add_enemy(Enemy::new(1, EnemyType::Tank, 1, (19, 0))); // Keeps the wave going
+
+        sim.step(500_000);
+        sim.enemies.retain(|e| e.id != 0);
+
+        let events = sim.step(100_000);
+        assert!(events.contains(&SimEvent::ProjectileMissed {
+            projectile_id: 0,
+            tower_id: 7,
+        }));
+        assert!(sim.projectiles.is_empty());
+    }
+
+    #[test]
+    fn test_lobbed_shell_lands_where_target_was() {
+        let mut sim = sim_with_enemy(EnemyType::FastRunner);
+        sim.config.projectiles = true;
+        sim.add_tower(Tower::new(0, (2, 13), TowerType::Cannon, 0));
+
+        // The runner outpaces the slow shell, which explodes on empty ground
+        let mut events = Vec::new();
+        for _ in 0..30 {
+            events.extend(sim.step(100_000));
+        }
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SimEvent::ProjectileLaunched { tower_id: 0, .. })));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SimEvent::ProjectileMissed { tower_id: 0, .. })));
+        assert_eq!(sim.enemies[0].health, sim.enemies[0].max_health);
+
+        // A stationary target is hit
+        let mut sim = sim_with_enemy(EnemyType::FastRunner);
+        sim.config.projectiles = true;
+        sim.enemies[0].apply_status(StatusEffect::new(StatusKind::Stun, 0, 10_000_000, 0));
+        sim.add_tower(Tower::new(0, (2, 13), TowerType::Cannon, 0));
+        for _ in 0..30 {
+            sim.step(100_000);
+        }
+        assert!(sim.enemies[0].health < sim.enemies[0].max_health);
+    }
+
+    #[test]
+    fn test_chain_lightning_attributes_every_kill() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        let spawn = sim.enemies[0].position;
+        for id in 1..3 {
+            let mut enemy = Enemy::new(id, EnemyType::BasicScout, 1, (0, 9));
+            enemy.position = (spawn.0, spawn.1 - 1000 * id as i32);
+            enemy.health = 5;
+            sim.add_enemy(enemy);
+        }
+        sim.add_tower(Tower::new(3, (1, 8), TowerType::Lightning, 0));
+
+        let events = sim.step(1_500_000);
+
+        let killed: Vec<u64> = events
+            .iter()
+            .filter_map(|e| match e {
+                SimEvent::EnemyKilled {
+                    enemy_id,
+                    tower_id: 3,
+                    ..
+                } => Some(*enemy_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(killed, vec![1, 2]);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SimEvent::EnemyDamaged { tower_id: 3, .. })));
+        assert_eq!(sim.enemies.len(), 1);
+        assert_eq!(sim.gold, 500 + 20);
+    }
+
+    #[test]
+    fn test_burn_kill_is_credited_to_source_tower() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.enemies[0].health = 6;
+        sim.enemies[0].apply_status(StatusEffect::new(StatusKind::Burn, 4, 2_000_000, 9));
+        sim.add_tower(Tower::new(9, (19, 0), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+        assert_eq!(
+            events,
+            vec![SimEvent::EnemyDamaged {
+                enemy_id: 0,
+                tower_id: 9,
+                damage: 4,
+            }]
+        );
+
+        let events = sim.step(500_000);
+        assert!(events.contains(&SimEvent::EnemyKilled {
+            enemy_id: 0,
+            tower_id: 9,
+            gold_reward: 10,
+        }));
+        assert_eq!(sim.towers[0].total_damage_dealt, 8);
+    }
+
+    #[test]
+    fn test_ice_slow_lasts_across_steps() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.add_tower(Tower::new(0, (1, 9), TowerType::Ice, 0));
+
+        sim.step(800_000);
+        assert_eq!(sim.enemies[0].slow_multiplier(), 700);
+
+        // Tower moved out of range: slow still applies until it expires
+        sim.towers.clear();
+        sim.step(1_000_000);
+        assert_eq!(sim.enemies[0].slow_multiplier(), 700);
+        sim.step(1_000_000);
+        assert_eq!(sim.enemies[0].slow_multiplier(), 1000);
+    }
+
+    #[test]
+    fn test_support_towers_in_step() {
+        let mut sim = sim_with_enemy(EnemyType::Tank);
+        sim.add_tower(Tower::new(0, (1, 9), TowerType::Arrow, 0));
+        sim.add_tower(Tower::new(1, (2, 9), TowerType::Banner, 0));
+        sim.add_tower(Tower::new(2, (10, 0), TowerType::GoldMine, 0));
+
+        let events = sim.step(500_000);
+        // Arrow vs tank: 12 buffed damage * 0.7 resistance = 8, minus 5 armor
+        assert!(events.contains(&SimEvent::TowerFired {
+            tower_id: 0,
+            target_id: 0,
+            damage: 3,
+        }));
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, SimEvent::TowerFired { tower_id: 1, .. })));
+
+        sim.time_micros = 9_500_000;
+        let events = sim.step(500_000);
+        assert!(events.contains(&SimEvent::GoldGenerated {
+            tower_id: 2,
+            amount: 15,
+        }));
+    }
+
+    #[test]
+    fn test_splitter_leaves_children() {
+        let mut sim = sim_with_enemy(EnemyType::Splitter);
+        sim.next_enemy_id = 1;
+        sim.enemies[0].health = 1;
+        sim.add_tower(Tower::new(0, (1, 9), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+
+        assert!(events.contains(&SimEvent::EnemySplit {
+            parent_id: 0,
+            child_id: 3,
+        }));
+        let ids: Vec<u64> = sim.enemies.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(sim.wave_active);
+        assert_eq!(sim.next_enemy_id, 4);
+    }
+
+    #[test]
+    fn test_boss_phases_in_step() {
+        let mut sim = sim_with_enemy(EnemyType::Boss);
+        sim.next_enemy_id = 1;
+        sim.add_tower(Tower::new(0, (1, 10), TowerType::Arrow, 0));
+        sim.add_tower(Tower::new(1, (15, 3), TowerType::Arrow, 0)); // Out of reach
+        sim.enemies[0].health = sim.enemies[0].max_health / 2;
+
+        let events = sim.step(100_000);
+
+        let phases: Vec<u8> = events
+            .iter()
+            .filter_map(|e| match e {
+                SimEvent::BossPhaseChanged { phase, .. } => Some(*phase),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(phases, vec![1, 2]);
+        assert!(events.contains(&SimEvent::MinionSummoned {
+            boss_id: 0,
+            minion_id: 3,
+        }));
+        assert!(events.contains(&SimEvent::TowerDisabled {
+            tower_id: 0,
+            until_micros: 3_100_000,
+        }));
+        assert_eq!(sim.enemies.len(), 4);
+        assert_eq!(sim.next_enemy_id, 4);
+
+        assert!(sim.enemies[0].is_immune());
+        assert!(!sim.towers[0].can_fire(1_000_000));
+        assert!(sim.towers[1].can_fire(1_000_000));
+    }
+
+    #[test]
+    fn test_healer_restores_nearby_enemies() {
+        let mut sim = sim_with_enemy(EnemyType::Healer);
+        let spawn = sim.grid.lane(0).spawn_point;
+        let mut wounded = Enemy::new(1, EnemyType::Tank, 1, spawn);
+        wounded.health = 100;
+        sim.add_enemy(wounded);
+
+        sim.step(1_000_000);
+        assert_eq!(sim.enemies[1].health, 100);
+
+        let events = sim.step(1_000_000);
+        assert!(events.contains(&SimEvent::EnemyHealed {
+            enemy_id: 1,
+            amount: 11,
+        }));
+        assert_eq!(sim.enemies[1].health, 111);
+    }
+
+    #[test]
+    fn test_wave_cleared_awards_bonus() {
+        let mut sim = sim_with_enemy(EnemyType::BasicScout);
+        sim.wave_number = 1;
+        sim.enemies[0].health = 1;
+        sim.add_tower(Tower::new(0, (1, 9), TowerType::Arrow, 0));
+
+        let events = sim.step(500_000);
+
+        assert_eq!(
+            events.last(),
+            Some(&SimEvent::WaveCleared {
+                wave_number: 1,
+                bonus_gold: 60,
+            })
+        );
+        assert!(!sim.wave_active);
+        assert_eq!(sim.gold, 500 + 10 + 60);
+    }
+
+    #[test]
+    fn test_flyer_takes_the_air_route() {
+        let mut sim = sim_with_enemy(EnemyType::Flyer);
+
+        // ~19.4 tiles straight to the base at 1.2 tiles/s
+        let mut events = Vec::new();
+        for _ in 0..16 {
+            events.extend(sim.step(1_000_000));
+        }
+        assert_eq!(sim.enemies.len(), 1);
+        assert_eq!(sim.enemies[0].path_index, 1);
+
+        events.extend(sim.step(1_000_000));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SimEvent::EnemyReachedBase { enemy_id: 0, .. })));
+    }
+
+    #[test]
+    fn test_enemy_reaching_base_damages_it() {
+        let mut sim = sim_with_enemy(EnemyType::Tank);
+        sim.base_health = 5;
+        sim.enemies[0].path_index = sim.grid.lane(0).path.len() - 1;
+        sim.enemies[0].position = tile_pos_to_milli(sim.grid.base_point);
+
+        let events = sim.step(100_000);
+
+        assert_eq!(
+            events,
+            vec![
+                SimEvent::EnemyReachedBase {
+                    enemy_id: 0,
+                    damage: 5,
+                    remaining_health: 0,
+                },
+                SimEvent::BaseDestroyed,
+            ]
+        );
+        assert!(sim.is_defeated());
+        assert!(sim.step(100_000).is_empty());
+    }
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        let mut a = Simulation::new(Grid::new(), 500, 20);
+        a.add_tower(Tower::new(0, (6, 9), TowerType::Arrow, 0));
+        a.add_tower(Tower::new(1, (8, 6), TowerType::Cannon, 0));
+        a.start_wave();
+        let mut b = a.clone();
+
+        for _ in 0..400 {
+            assert_eq!(a.step(100_000), b.step(100_000));
+        }
+        assert_eq!(a.gold, b.gold);
+        assert_eq!(a.base_health, b.base_health);
+        assert_eq!(a.enemies.len(), b.enemies.len());
+    }
+}
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+/// Interval between damage-over-time ticks, in microseconds
+pub const DOT_TICK_MICROS: u64 = 500_000;
+
+/// Max concurrent poison stacks on one enemy
+pub const MAX_POISON_STACKS: usize = 5;
+
+/// Max concurrent armor shred stacks on one enemy
+pub const MAX_ARMOR_SHRED_STACKS: usize = 3;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    /// Reduces speed by `magnitude` per-mille
+    Slow,
+    /// Deals `magnitude` damage every DoT tick
+    Burn,
+    /// Deals `magnitude` damage every DoT tick, stacking
+    Poison,
+    /// Stops movement entirely
+    Stun,
+    /// Increases damage taken by `magnitude` per-mille, stacking
+    ArmorShred,
+    /// Ignores all damage
+    Immune,
+}
+
+impl StatusKind {
+    /// How many instances of this effect can be active at once
+    pub fn max_stacks(&self) -> usize {
+        match self {
+            Self::Poison => MAX_POISON_STACKS,
+            Self::ArmorShred => MAX_ARMOR_SHRED_STACKS,
+            Self::Slow | Self::Burn | Self::Stun | Self::Immune => 1,
+        }
+    }
+
+    pub fn deals_damage(&self) -> bool {
+        matches!(self, Self::Burn | Self::Poison)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub magnitude: u32,
+    pub remaining_micros: u64,
+    /// Tower that applied the effect, credited for its damage
+    pub source_tower_id: u64,
+    /// Time accumulated towards the next DoT tick
+    pub tick_progress_micros: u64,
+}
+
+impl StatusEffect {
+    pub fn new(
+        kind: StatusKind,
+        magnitude: u32,
+        duration_micros: u64,
+        source_tower_id: u64,
+    ) -> Self {
+        Self {
+            kind,
+            magnitude,
+            remaining_micros: duration_micros,
+            source_tower_id,
+            tick_progress_micros: 0,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining_micros == 0
+    }
+
+    /// Advance the effect by up to `delta_micros`, returning the DoT damage dealt
+    pub fn advance(&mut self, delta_micros: u64) -> u32 {
+        let elapsed = delta_micros.min(self.remaining_micros);
+        self.remaining_micros -= elapsed;
+
+        if !self.kind.deals_damage() {
+            return 0;
+        }
+
+        self.tick_progress_micros = self.tick_progress_micros.saturating_add(elapsed);
+        let ticks = self.tick_progress_micros / DOT_TICK_MICROS;
+        self.tick_progress_micros %= DOT_TICK_MICROS;
+        self.magnitude
+            .saturating_mul(ticks.min(u32::MAX as u64) as u32)
+    }
+}
+
+/// Add an effect to a list, following the kind's stacking rule
+///
+/// Single-instance kinds keep the stronger magnitude and the longer duration.
+/// Stacking kinds add a new instance; at the cap, the one closest to expiring
+/// is replaced.
+pub fn add_status_effect(effects: &mut Vec<StatusEffect>, effect: StatusEffect) {
+    let max_stacks = effect.kind.max_stacks();
+
+    if max_stacks == 1 {
+        if let Some(existing) = effects.iter_mut().find(|e| e.kind == effect.kind) {
+            if effect.magnitude >= existing.magnitude {
+                existing.magnitude = effect.magnitude;
+                existing.source_tower_id = effect.source_tower_id;
+            }
+            existing.remaining_micros = existing.remaining_micros.max(effect.remaining_micros);
+            return;
+        }
+        effects.push(effect);
+        return;
+    }
+
+    let stacks = effects.iter().filter(|e| e.kind == effect.kind).count();
+    if stacks < max_stacks {
+        effects.push(effect);
+        return;
+    }
+
+    // Replace the stack closest to expiring (first one on ties)
+    if let Some(oldest) = effects
+        .iter_mut()
+        .filter(|e| e.kind == effect.kind)
+        .min_by_key(|e| e.remaining_micros)
+    {
+        *oldest = effect;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_instance_refresh() {
+        let mut effects = Vec::new();
+        add_status_effect(
+            &mut effects,
+            StatusEffect::new(StatusKind::Slow, 300, 2_000_000, 0),
+        );
+        add_status_effect(
+            &mut effects,
+            StatusEffect::new(StatusKind::Slow, 200, 3_000_000, 1),
+        );
+
+        assert_eq!(effects.len(), 1);
+        // Stronger slow kept, longer duration kept
+        assert_eq!(effects[0].magnitude, 300);
+        assert_eq!(effects[0].source_tower_id, 0);
+        assert_eq!(effects[0].remaining_micros, 3_000_000);
+    }
+
+    #[test]
+    fn test_poison_stacks_up_to_cap() {
+        let mut effects = Vec::new();
+        for i in 0..MAX_POISON_STACKS as u64 + 2 {
+            let duration = 1_000_000 + i * 100_000;
+            add_status_effect(
+                &mut effects,
+                StatusEffect::new(StatusKind::Poison, 2, duration, i),
+            );
+        }
+
+        assert_eq!(effects.len(), MAX_POISON_STACKS);
+        // The two shortest stacks were replaced by the newest ones
+        assert!(effects.iter().all(|e| e.source_tower_id >= 2));
+    }
+
+    #[test]
+    fn test_dot_ticks() {
+        let mut burn = StatusEffect::new(StatusKind::Burn, 4, 1_200_000, 0);
+
+        assert_eq!(burn.advance(400_000), 0);
+        assert_eq!(burn.advance(600_000), 8); // 1.0s elapsed: 2 ticks
+        assert_eq!(burn.advance(1_000_000), 0); // Only 0.2s left, no full tick
+        assert!(burn.is_expired());
+
+        let mut stun = StatusEffect::new(StatusKind::Stun, 0, 500_000, 0);
+        assert_eq!(stun.advance(1_000_000), 0);
+        assert!(stun.is_expired());
+    }
+}
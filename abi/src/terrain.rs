@@ -0,0 +1,138 @@
+use crate::fixed::PERMILLE;
+use crate::tower::TowerType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Terrain of the tiles that aren't plain ground, keyed by tile
+pub type TerrainMap = BTreeMap<(u8, u8), Terrain>;
+
+/// Extra range of a tower on high ground, in tiles
+pub const HIGH_GROUND_RANGE_BONUS: u8 = 1;
+
+/// Extra damage of a tower on a rune, in percent
+pub const RUNE_DAMAGE_BONUS_PERCENT: u32 = 25;
+
+/// What a tile is made of: how fast ground enemies cross it, and what towers
+/// may stand on it
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    #[default]
+    Grass,
+    /// Slows enemies to half speed
+    Mud,
+    /// Enemies run 50% faster
+    Road,
+    /// Enemies cross 25% faster
+    Bridge,
+    /// Towers here reach one tile further
+    HighGround,
+    /// Only Ice and Magic towers can be built here
+    Water,
+    /// Nothing can be built here
+    Rock,
+    /// Towers here deal 25% more damage
+    Rune,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 8] = [
+        Self::Grass,
+        Self::Mud,
+        Self::Road,
+        Self::Bridge,
+        Self::HighGround,
+        Self::Water,
+        Self::Rock,
+        Self::Rune,
+    ];
+
+    /// Ground movement speed on this terrain, per-mille of the enemy's speed
+    pub fn speed_permille(&self) -> u32 {
+        match self {
+            Self::Mud => 500,
+            Self::Road => 1500,
+            Self::Bridge => 1250,
+            Self::Grass | Self::HighGround | Self::Water | Self::Rock | Self::Rune => 1000,
+        }
+    }
+
+    /// Whether any tower can be built here
+    pub fn is_buildable(&self) -> bool {
+        *self != Self::Rock
+    }
+
+    /// Whether a tower of this type can be built here
+    pub fn allows_tower(&self, tower_type: TowerType) -> bool {
+        match self {
+            Self::Rock => false,
+            Self::Water => matches!(tower_type, TowerType::Ice | TowerType::Magic),
+            _ => true,
+        }
+    }
+
+    /// Extra tiles of range for a tower standing here
+    pub fn range_bonus(&self) -> u8 {
+        match self {
+            Self::HighGround => HIGH_GROUND_RANGE_BONUS,
+            _ => 0,
+        }
+    }
+
+    /// Percent more damage for a tower standing here
+    pub fn damage_bonus_percent(&self) -> u32 {
+        match self {
+            Self::Rune => RUNE_DAMAGE_BONUS_PERCENT,
+            _ => 0,
+        }
+    }
+
+    /// Pathfinding cost of entering this tile, per-mille of a grass step.
+    /// The inverse of its speed, so the cheapest route is also the fastest.
+    pub fn cost_permille(&self) -> u32 {
+        PERMILLE * PERMILLE / self.speed_permille()
+    }
+
+    /// Lowest tile cost of any terrain, which keeps distance estimates admissible
+    pub fn cheapest_cost_permille() -> u32 {
+        Self::ALL
+            .iter()
+            .map(Terrain::cost_permille)
+            .min()
+            .unwrap_or(PERMILLE)
+    }
+}
+
+/// Terrain of a tile (grass unless the map says otherwise)
+pub fn terrain_at(terrain: &TerrainMap, tile: (u8, u8)) -> Terrain {
+    terrain.get(&tile).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terrain_costs() {
+        assert_eq!(Terrain::Grass.cost_permille(), 1000);
+        assert_eq!(Terrain::Mud.cost_permille(), 2000);
+        assert_eq!(Terrain::Road.cost_permille(), 666);
+        assert_eq!(Terrain::cheapest_cost_permille(), 666);
+
+        let mut terrain = TerrainMap::new();
+        terrain.insert((3, 4), Terrain::Bridge);
+        assert_eq!(terrain_at(&terrain, (3, 4)), Terrain::Bridge);
+        assert_eq!(terrain_at(&terrain, (4, 4)), Terrain::Grass);
+    }
+
+    #[test]
+    fn test_tower_placement_rules() {
+        assert!(Terrain::Grass.allows_tower(TowerType::Cannon));
+        assert!(Terrain::Water.allows_tower(TowerType::Ice));
+        assert!(!Terrain::Water.allows_tower(TowerType::Arrow));
+        assert!(!Terrain::Rock.is_buildable());
+        assert!(!Terrain::Rock.allows_tower(TowerType::Ice));
+        assert_eq!(Terrain::HighGround.range_bonus(), 1);
+        assert_eq!(Terrain::Rune.damage_bonus_percent(), 25);
+        assert_eq!(Terrain::Rune.range_bonus(), 0);
+    }
+}
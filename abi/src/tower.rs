@@ -1,3 +1,7 @@
+use crate::enemy::MovementClass;
+use crate::fixed::{distance, tile_pos_to_milli, within_radius, MILLI_PER_TILE};
+use crate::projectile::ProjectileMotion;
+use crate::terrain::Terrain;
 use async_graphql::Enum;
 use serde::{Deserialize, Serialize};
 
@@ -8,9 +12,128 @@ pub enum TowerType {
     Magic,
     Ice,
     Lightning,
+    /// Support: boosts damage of nearby towers
+    Banner,
+    /// Support: extends range of nearby towers
+    Watchtower,
+    /// Support: speeds up firing of nearby towers
+    WarDrum,
+    /// Support: generates gold during waves
+    GoldMine,
+}
+
+/// Buff a support tower gives to towers within its range
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum AuraKind {
+    /// `aura_strength` percent more damage
+    Damage,
+    /// `aura_strength` extra tiles of range
+    Range,
+    /// `aura_strength` percent faster firing
+    FireRate,
+}
+
+/// Level at which a tower picks one of its two specialization branches
+pub const SPECIALIZATION_LEVEL: u8 = 4;
+
+/// Damage multiplier of a critical hit, in per-mille
+pub const CRIT_DAMAGE_PERMILLE: u32 = 2000;
+
+/// Level-4 branch a tower commits to; each tower type has two
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Enum)]
+pub enum Specialization {
+    /// Arrow: slow, long-range, heavy shots
+    Sniper,
+    /// Arrow: very fast light shots
+    RapidFire,
+    /// Cannon: long-range shells with a wide blast
+    Mortar,
+    /// Cannon: full damage to everything in the blast
+    Cluster,
+    /// Magic: heavy single-target damage
+    Arcane,
+    /// Magic: hits shred armor
+    Hex,
+    /// Ice: hard slow on everything around the target
+    Glacier,
+    /// Ice: hits briefly stun
+    Frostbite,
+    /// Lightning: long chains that barely fade
+    Storm,
+    /// Lightning: huge stunning hits with a short chain
+    Overload,
+}
+
+impl Specialization {
+    pub fn tower_type(&self) -> TowerType {
+        match self {
+            Self::Sniper | Self::RapidFire => TowerType::Arrow,
+            Self::Mortar | Self::Cluster => TowerType::Cannon,
+            Self::Arcane | Self::Hex => TowerType::Magic,
+            Self::Glacier | Self::Frostbite => TowerType::Ice,
+            Self::Storm | Self::Overload => TowerType::Lightning,
+        }
+    }
+}
+
+/// Which enemy in range a tower shoots at
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Enum)]
+pub enum TargetPriority {
+    /// Closest to the base along its route
+    #[default]
+    First,
+    /// Furthest from the base along its route
+    Last,
+    /// Most remaining health
+    Strongest,
+    /// Least remaining health
+    Weakest,
+    /// Nearest to the tower
+    Closest,
+    /// Highest current speed
+    Fastest,
+    /// Bosses only, closest to the base
+    BossOnly,
+}
+
+/// Kind of damage a tower deals, resisted differently by each enemy type
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Physical,
+    Magic,
+    Ice,
+    Lightning,
+    Explosive,
+}
+
+impl DamageType {
+    pub const ALL: [DamageType; 5] = [
+        Self::Physical,
+        Self::Magic,
+        Self::Ice,
+        Self::Lightning,
+        Self::Explosive,
+    ];
+
+    /// Whether flat enemy armor reduces this damage
+    pub fn is_reduced_by_armor(&self) -> bool {
+        matches!(self, Self::Physical | Self::Explosive)
+    }
 }
 
 impl TowerType {
+    pub const ALL: [TowerType; 9] = [
+        Self::Arrow,
+        Self::Cannon,
+        Self::Magic,
+        Self::Ice,
+        Self::Lightning,
+        Self::Banner,
+        Self::Watchtower,
+        Self::WarDrum,
+        Self::GoldMine,
+    ];
+
     pub fn cost(&self) -> u64 {
         match self {
             Self::Arrow => 100,
@@ -18,6 +141,58 @@ impl TowerType {
             Self::Magic => 200,
             Self::Ice => 150,
             Self::Lightning => 300,
+            Self::Banner => 200,
+            Self::Watchtower => 175,
+            Self::WarDrum => 225,
+            Self::GoldMine => 300,
+        }
+    }
+
+    /// Support towers never attack; they buff others or earn gold
+    pub fn is_support(&self) -> bool {
+        matches!(
+            self,
+            Self::Banner | Self::Watchtower | Self::WarDrum | Self::GoldMine
+        )
+    }
+
+    /// Whether this tower can attack enemies walking the ground path
+    pub fn targets_ground(&self) -> bool {
+        !self.is_support()
+    }
+
+    /// Whether this tower can attack flying enemies
+    pub fn targets_air(&self) -> bool {
+        matches!(self, Self::Arrow | Self::Magic | Self::Lightning)
+    }
+
+    /// How this tower's shots travel and their speed in milli-tiles per second,
+    /// or None if they land instantly
+    pub fn projectile(&self) -> Option<(ProjectileMotion, u32)> {
+        match self {
+            Self::Arrow => Some((ProjectileMotion::Homing, 15_000)),
+            Self::Cannon => Some((ProjectileMotion::Lobbed, 5_000)),
+            Self::Magic => Some((ProjectileMotion::Homing, 10_000)),
+            Self::Ice => Some((ProjectileMotion::Homing, 8_000)),
+            Self::Lightning => None,
+            Self::Banner | Self::Watchtower | Self::WarDrum | Self::GoldMine => None,
+        }
+    }
+
+    /// Whether this tower can attack an enemy of the given movement class
+    pub fn can_target(&self, movement: MovementClass) -> bool {
+        match movement {
+            MovementClass::Ground => self.targets_ground(),
+            MovementClass::Air => self.targets_air(),
+        }
+    }
+
+    pub fn aura_kind(&self) -> Option<AuraKind> {
+        match self {
+            Self::Banner => Some(AuraKind::Damage),
+            Self::Watchtower => Some(AuraKind::Range),
+            Self::WarDrum => Some(AuraKind::FireRate),
+            _ => None,
         }
     }
 
@@ -25,28 +200,130 @@ impl TowerType {
         match self {
             Self::Arrow => TowerStats {
                 damage: 10,
+                damage_type: DamageType::Physical,
                 range: 3,
                 fire_rate_ms: 500,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 0,
+                gold_income: 0,
+                crit_chance_permille: 100,
             },
             Self::Cannon => TowerStats {
                 damage: 50,
+                damage_type: DamageType::Explosive,
                 range: 4,
                 fire_rate_ms: 2000,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 1500,
+                splash_percent: 50,
+                aura_strength: 0,
+                gold_income: 0,
+                crit_chance_permille: 50,
             },
             Self::Magic => TowerStats {
                 damage: 15,
+                damage_type: DamageType::Magic,
                 range: 2,
                 fire_rate_ms: 1000,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 0,
+                gold_income: 0,
+                crit_chance_permille: 50,
             },
             Self::Ice => TowerStats {
                 damage: 5,
+                damage_type: DamageType::Ice,
                 range: 3,
                 fire_rate_ms: 800,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 0,
+                gold_income: 0,
+                crit_chance_permille: 0,
             },
             Self::Lightning => TowerStats {
                 damage: 30,
+                damage_type: DamageType::Lightning,
                 range: 3,
                 fire_rate_ms: 1500,
+                chain_targets: 3,
+                chain_range: 2000,
+                chain_falloff_percent: 70,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 0,
+                gold_income: 0,
+                crit_chance_permille: 50,
+            },
+            Self::Banner => TowerStats {
+                damage: 0,
+                damage_type: DamageType::Physical,
+                range: 2,
+                fire_rate_ms: 0,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 20,
+                gold_income: 0,
+                crit_chance_permille: 0,
+            },
+            Self::Watchtower => TowerStats {
+                damage: 0,
+                damage_type: DamageType::Physical,
+                range: 2,
+                fire_rate_ms: 0,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 1,
+                gold_income: 0,
+                crit_chance_permille: 0,
+            },
+            Self::WarDrum => TowerStats {
+                damage: 0,
+                damage_type: DamageType::Physical,
+                range: 2,
+                fire_rate_ms: 0,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 15,
+                gold_income: 0,
+                crit_chance_permille: 0,
+            },
+            // Pays out gold_income every fire_rate_ms
+            Self::GoldMine => TowerStats {
+                damage: 0,
+                damage_type: DamageType::Physical,
+                range: 0,
+                fire_rate_ms: 10_000,
+                chain_targets: 0,
+                chain_range: 0,
+                chain_falloff_percent: 0,
+                splash_radius: 0,
+                splash_percent: 0,
+                aura_strength: 0,
+                gold_income: 15,
+                crit_chance_permille: 0,
             },
         }
     }
@@ -63,10 +340,35 @@ impl TowerType {
             (Self::Ice, 3) => Some(350),
             (Self::Lightning, 2) => Some(400),
             (Self::Lightning, 3) => Some(600),
+            (Self::Arrow, 4) => Some(400),
+            (Self::Cannon, 4) => Some(750),
+            (Self::Magic, 4) => Some(600),
+            (Self::Ice, 4) => Some(500),
+            (Self::Lightning, 4) => Some(900),
+            (Self::Banner, 2) => Some(150),
+            (Self::Banner, 3) => Some(250),
+            (Self::Watchtower, 2) => Some(125),
+            (Self::Watchtower, 3) => Some(200),
+            (Self::WarDrum, 2) => Some(175),
+            (Self::WarDrum, 3) => Some(275),
+            (Self::GoldMine, 2) => Some(250),
+            (Self::GoldMine, 3) => Some(400),
             _ => None,
         }
     }
 
+    /// The level-4 branches available to this tower type (none for support towers)
+    pub fn specializations(&self) -> &'static [Specialization] {
+        match self {
+            Self::Arrow => &[Specialization::Sniper, Specialization::RapidFire],
+            Self::Cannon => &[Specialization::Mortar, Specialization::Cluster],
+            Self::Magic => &[Specialization::Arcane, Specialization::Hex],
+            Self::Ice => &[Specialization::Glacier, Specialization::Frostbite],
+            Self::Lightning => &[Specialization::Storm, Specialization::Overload],
+            Self::Banner | Self::Watchtower | Self::WarDrum | Self::GoldMine => &[],
+        }
+    }
+
     pub fn sell_value(&self, level: u8) -> u64 {
         let mut total_cost = self.cost();
         for lvl in 2..=level {
@@ -74,15 +376,32 @@ impl TowerType {
                 total_cost = total_cost.saturating_add(upgrade_cost);
             }
         }
-        ((total_cost as f64) * 0.7) as u64
+        total_cost.saturating_mul(7) / 10
     }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TowerStats {
     pub damage: u32,
+    pub damage_type: DamageType,
     pub range: u8,
     pub fire_rate_ms: u64,
+    /// Extra enemies a shot jumps to after the primary target
+    pub chain_targets: u8,
+    /// Max jump distance between chained enemies, in milli-tiles
+    pub chain_range: u32,
+    /// Percent of the previous hit's damage kept on each jump
+    pub chain_falloff_percent: u32,
+    /// Splash radius around the primary target, in milli-tiles
+    pub splash_radius: u32,
+    /// Percent of the primary damage dealt to splashed enemies
+    pub splash_percent: u32,
+    /// Support towers: buff size for their `AuraKind`
+    pub aura_strength: u32,
+    /// Gold mines: gold earned per `fire_rate_ms` during a wave
+    pub gold_income: u64,
+    /// Chance in 1000 that a shot deals `CRIT_DAMAGE_PERMILLE` damage
+    pub crit_chance_permille: u32,
 }
 
 impl TowerStats {
@@ -92,16 +411,83 @@ impl TowerStats {
             2 => Self {
                 damage: self.damage.saturating_add(self.damage / 2), // +50%
                 range: self.range,
-                fire_rate_ms: ((self.fire_rate_ms as f32) * 0.9) as u64, // -10% (faster)
+                fire_rate_ms: self.fire_rate_ms.saturating_mul(9) / 10, // -10% (faster)
+                aura_strength: self.aura_strength.saturating_add(self.aura_strength / 2),
+                gold_income: self.gold_income.saturating_add(self.gold_income / 2),
+                ..*self
             },
             3 => Self {
                 damage: self.damage.saturating_mul(2), // +100%
                 range: self.range.saturating_add(1),
-                fire_rate_ms: ((self.fire_rate_ms as f32) * 0.8) as u64, // -20% (faster)
+                fire_rate_ms: self.fire_rate_ms.saturating_mul(8) / 10, // -20% (faster)
+                chain_targets: if self.chain_targets > 0 {
+                    self.chain_targets.saturating_add(1)
+                } else {
+                    0
+                },
+                aura_strength: self.aura_strength.saturating_mul(2),
+                gold_income: self.gold_income.saturating_mul(2),
+                ..*self
             },
             _ => *self,
         }
     }
+
+    /// Level-4 stats for a branch, applied on top of level-3 stats
+    pub fn specialized(&self, specialization: Specialization) -> Self {
+        match specialization {
+            Specialization::Sniper => Self {
+                damage: self.damage.saturating_mul(4),
+                crit_chance_permille: self.crit_chance_permille.saturating_add(150),
+                range: self.range.saturating_add(3),
+                fire_rate_ms: self.fire_rate_ms.saturating_mul(2),
+                ..*self
+            },
+            Specialization::RapidFire => Self {
+                fire_rate_ms: self.fire_rate_ms / 3,
+                ..*self
+            },
+            Specialization::Mortar => Self {
+                range: self.range.saturating_add(3),
+                fire_rate_ms: self.fire_rate_ms.saturating_mul(5) / 4,
+                splash_radius: 2500,
+                ..*self
+            },
+            Specialization::Cluster => Self {
+                damage: self.damage.saturating_add(self.damage / 2),
+                splash_percent: 100,
+                ..*self
+            },
+            Specialization::Arcane => Self {
+                damage: self.damage.saturating_mul(5) / 2,
+                range: self.range.saturating_add(1),
+                ..*self
+            },
+            // Armor shred is applied on hit in combat
+            Specialization::Hex => *self,
+            // Splash spreads the slow to every enemy near the target
+            Specialization::Glacier => Self {
+                splash_radius: 1500,
+                splash_percent: 100,
+                ..*self
+            },
+            Specialization::Frostbite => Self {
+                damage: self.damage.saturating_mul(3),
+                ..*self
+            },
+            Specialization::Storm => Self {
+                chain_targets: self.chain_targets.saturating_add(3),
+                chain_range: 3000,
+                chain_falloff_percent: 85,
+                ..*self
+            },
+            Specialization::Overload => Self {
+                damage: self.damage.saturating_mul(3),
+                chain_targets: 1,
+                ..*self
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,9 +496,19 @@ pub struct Tower {
     pub position: (u8, u8),
     pub tower_type: TowerType,
     pub level: u8,
+    /// Stats from type, level and specialization alone
+    pub base_stats: TowerStats,
+    /// Stats used in combat: base stats plus support auras
     pub stats: TowerStats,
+    pub target_priority: TargetPriority,
+    /// Branch chosen at level 4, if any
+    pub specialization: Option<Specialization>,
     pub last_shot_micros: u64,
     pub total_damage_dealt: u64,
+    /// Simulation time until which a boss has switched this tower off
+    pub disabled_until_micros: u64,
+    /// Ground the tower stands on, which may add range or damage
+    pub terrain: Terrain,
 }
 
 impl Tower {
@@ -122,14 +518,30 @@ impl Tower {
             position,
             tower_type,
             level: 1,
+            base_stats: tower_type.base_stats(),
             stats: tower_type.base_stats(),
+            target_priority: TargetPriority::First,
+            specialization: None,
             last_shot_micros: current_time_micros,
             total_damage_dealt: 0,
+            disabled_until_micros: 0,
+            terrain: Terrain::Grass,
         }
     }
 
     pub fn can_fire(&self, current_time_micros: u64) -> bool {
-        current_time_micros.saturating_sub(self.last_shot_micros) >= self.stats.fire_rate_ms.saturating_mul(1000)
+        !self.is_disabled(current_time_micros)
+            && current_time_micros.saturating_sub(self.last_shot_micros)
+                >= self.stats.fire_rate_ms.saturating_mul(1000)
+    }
+
+    pub fn is_disabled(&self, current_time_micros: u64) -> bool {
+        current_time_micros < self.disabled_until_micros
+    }
+
+    /// Stop the tower firing until `until_micros`, never shortening an existing disable
+    pub fn disable_until(&mut self, until_micros: u64) {
+        self.disabled_until_micros = self.disabled_until_micros.max(until_micros);
     }
 
     pub fn upgrade(&mut self) -> Result<(), &'static str> {
@@ -137,18 +549,56 @@ impl Tower {
             return Err("Tower already at max level");
         }
         self.level = self.level.saturating_add(1);
-        self.stats = self.tower_type.base_stats().upgraded(self.level);
+        self.base_stats = self.tower_type.base_stats().upgraded(self.level);
+        self.stats = self.base_stats;
+        Ok(())
+    }
+
+    /// Upgrade a level-3 tower to level 4 along one of its branches
+    pub fn specialize(&mut self, specialization: Specialization) -> Result<(), &'static str> {
+        if specialization.tower_type() != self.tower_type {
+            return Err("Specialization does not belong to this tower type");
+        }
+        if self.level < 3 {
+            return Err("Tower must be level 3 to specialize");
+        }
+        if self.specialization.is_some() {
+            return Err("Tower already specialized");
+        }
+        self.level = SPECIALIZATION_LEVEL;
+        self.specialization = Some(specialization);
+        self.base_stats = self
+            .tower_type
+            .base_stats()
+            .upgraded(3)
+            .specialized(specialization);
+        self.stats = self.base_stats;
         Ok(())
     }
 
-    pub fn distance_to(&self, pos: (f32, f32)) -> f32 {
-        let dx = pos.0 - (self.position.0 as f32);
-        let dy = pos.1 - (self.position.1 as f32);
-        (dx * dx + dy * dy).sqrt()
+    /// Tower position in milli-tiles
+    pub fn center(&self) -> (i32, i32) {
+        tile_pos_to_milli(self.position)
+    }
+
+    /// Distance to a milli-tile position, in milli-tiles
+    pub fn distance_to(&self, pos: (i32, i32)) -> u32 {
+        distance(self.center(), pos)
     }
 
-    pub fn in_range(&self, pos: (f32, f32)) -> bool {
-        self.distance_to(pos) <= (self.stats.range as f32)
+    pub fn in_range(&self, pos: (i32, i32)) -> bool {
+        let range = (self.stats.range as u32) * (MILLI_PER_TILE as u32);
+        within_radius(self.center(), pos, range)
+    }
+
+    /// Whether a support tower's aura reaches a tile position
+    pub fn aura_covers(&self, position: (u8, u8)) -> bool {
+        let range = self
+            .base_stats
+            .range
+            .saturating_add(self.terrain.range_bonus());
+        let radius = (range as u32) * (MILLI_PER_TILE as u32);
+        within_radius(self.center(), tile_pos_to_milli(position), radius)
     }
 
     pub fn record_damage(&mut self, damage: u64) {
@@ -174,7 +624,8 @@ mod tests {
         let tower_type = TowerType::Arrow;
         assert_eq!(tower_type.upgrade_cost(2), Some(150));
         assert_eq!(tower_type.upgrade_cost(3), Some(250));
-        assert_eq!(tower_type.upgrade_cost(4), None);
+        assert_eq!(tower_type.upgrade_cost(4), Some(400));
+        assert_eq!(tower_type.upgrade_cost(5), None);
     }
 
     #[test]
@@ -186,6 +637,8 @@ mod tests {
         assert_eq!(tower_type.sell_value(2), 175);
         // Level 3: (100 + 150 + 250) * 0.7 = 350
         assert_eq!(tower_type.sell_value(3), 350);
+        // Level 4: (100 + 150 + 250 + 400) * 0.7 = 630
+        assert_eq!(tower_type.sell_value(4), 630);
     }
 
     #[test]
@@ -201,6 +654,25 @@ mod tests {
         assert_eq!(lvl3_stats.range, 4); // 3 + 1
     }
 
+    #[test]
+    fn test_multi_target_stats() {
+        let arrow = TowerType::Arrow.base_stats();
+        assert_eq!(arrow.chain_targets, 0);
+        assert_eq!(arrow.splash_radius, 0);
+
+        assert_eq!(arrow.damage_type, DamageType::Physical);
+
+        let cannon = TowerType::Cannon.base_stats();
+        assert_eq!(cannon.damage_type, DamageType::Explosive);
+        assert_eq!(cannon.splash_radius, 1500);
+        assert_eq!(cannon.upgraded(3).splash_percent, 50);
+
+        let lightning = TowerType::Lightning.base_stats();
+        assert_eq!(lightning.chain_targets, 3);
+        assert_eq!(lightning.upgraded(2).chain_targets, 3);
+        assert_eq!(lightning.upgraded(3).chain_targets, 4);
+    }
+
     #[test]
     fn test_tower_creation() {
         let tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
@@ -208,15 +680,18 @@ mod tests {
         assert_eq!(tower.position, (5, 5));
         assert_eq!(tower.level, 1);
         assert_eq!(tower.stats.damage, 10);
+        assert_eq!(tower.target_priority, TargetPriority::First);
     }
 
     #[test]
     fn test_tower_in_range() {
         let tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
         // Range is 3 tiles
-        assert!(tower.in_range((5.0, 7.0))); // Within range
-        assert!(tower.in_range((8.0, 5.0))); // Within range
-        assert!(!tower.in_range((10.0, 10.0))); // Out of range
+        assert!(tower.in_range((5000, 7000))); // Within range
+        assert!(tower.in_range((8000, 5000))); // Within range
+        assert!(!tower.in_range((8000, 5001))); // Just outside range
+        assert!(!tower.in_range((10000, 10000))); // Out of range
+        assert_eq!(tower.distance_to((8000, 9000)), 5000);
     }
 
     #[test]
@@ -240,6 +715,81 @@ mod tests {
         assert_eq!(tower.level, 3);
     }
 
+    #[test]
+    fn test_tower_specialize() {
+        let mut tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
+
+        // Must reach level 3 first, and the branch must match the type
+        assert!(tower.specialize(Specialization::Sniper).is_err());
+        tower.upgrade().unwrap();
+        tower.upgrade().unwrap();
+        assert!(tower.specialize(Specialization::Mortar).is_err());
+
+        assert!(tower.specialize(Specialization::Sniper).is_ok());
+        assert_eq!(tower.level, 4);
+        assert_eq!(tower.specialization, Some(Specialization::Sniper));
+        assert_eq!(tower.stats.damage, 80); // 20 * 4
+        assert_eq!(tower.stats.range, 7); // 4 + 3
+
+        // Branch is final
+        assert!(tower.specialize(Specialization::RapidFire).is_err());
+        assert!(tower.upgrade().is_err());
+    }
+
+    #[test]
+    fn test_specializations_are_distinct() {
+        for tower_type in [
+            TowerType::Arrow,
+            TowerType::Cannon,
+            TowerType::Magic,
+            TowerType::Ice,
+            TowerType::Lightning,
+        ] {
+            let [a, b] = tower_type.specializations() else {
+                panic!("{:?} should have two branches", tower_type);
+            };
+            assert_ne!(a, b);
+            assert_eq!(a.tower_type(), tower_type);
+            assert_eq!(b.tower_type(), tower_type);
+        }
+
+        assert!(TowerType::GoldMine.specializations().is_empty());
+
+        let level3 = TowerType::Lightning.base_stats().upgraded(3);
+        let storm = level3.specialized(Specialization::Storm);
+        let overload = level3.specialized(Specialization::Overload);
+        assert_eq!(storm.chain_targets, 7);
+        assert_eq!(overload.chain_targets, 1);
+        assert!(overload.damage > storm.damage);
+    }
+
+    #[test]
+    fn test_support_towers() {
+        assert!(TowerType::Banner.is_support());
+        assert!(!TowerType::Arrow.is_support());
+        assert_eq!(TowerType::WarDrum.aura_kind(), Some(AuraKind::FireRate));
+        assert_eq!(TowerType::GoldMine.aura_kind(), None);
+
+        let banner = TowerType::Banner.base_stats();
+        assert_eq!(banner.upgraded(2).aura_strength, 30);
+        assert_eq!(banner.upgraded(3).aura_strength, 40);
+        assert_eq!(TowerType::GoldMine.base_stats().upgraded(3).gold_income, 30);
+
+        let tower = Tower::new(0, (5, 5), TowerType::Banner, 0);
+        assert!(tower.aura_covers((7, 5)));
+        assert!(!tower.aura_covers((7, 6)));
+    }
+
+    #[test]
+    fn test_anti_air_flags() {
+        assert!(TowerType::Arrow.can_target(MovementClass::Air));
+        assert!(TowerType::Lightning.can_target(MovementClass::Air));
+        assert!(!TowerType::Cannon.can_target(MovementClass::Air));
+        assert!(!TowerType::Ice.can_target(MovementClass::Air));
+        assert!(TowerType::Cannon.can_target(MovementClass::Ground));
+        assert!(!TowerType::Banner.can_target(MovementClass::Ground));
+    }
+
     #[test]
     fn test_tower_can_fire() {
         let tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
@@ -248,4 +798,14 @@ mod tests {
         assert!(tower.can_fire(500_000)); // Can fire after 500ms
         assert!(!tower.can_fire(400_000)); // Cannot fire before cooldown
     }
+
+    #[test]
+    fn test_tower_disable() {
+        let mut tower = Tower::new(0, (5, 5), TowerType::Arrow, 0);
+        tower.disable_until(2_000_000);
+        tower.disable_until(1_000_000); // Never shortens
+
+        assert!(!tower.can_fire(1_500_000));
+        assert!(tower.can_fire(2_000_000));
+    }
 }
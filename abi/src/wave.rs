@@ -1,11 +1,17 @@
+use crate::difficulty::Difficulty;
 use crate::enemy::{Enemy, EnemyType};
+use crate::mutator::{validate_mutators, Mutator};
+use crate::rng::GameRng;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Parameters for procedurally generated waves (used when a game has no wave script)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WaveConfig {
     pub base_enemy_count: u32,
     pub enemy_count_per_wave: u32,
     pub boss_wave_interval: u32,
+    /// Each enemy type's count varies randomly by up to this percent either way
+    pub variance_percent: u32,
 }
 
 impl Default for WaveConfig {
@@ -14,125 +20,420 @@ impl Default for WaveConfig {
             base_enemy_count: 10,
             enemy_count_per_wave: 2,
             boss_wave_interval: 10,
+            variance_percent: 20,
         }
     }
 }
 
-pub fn generate_wave(wave_number: u32, spawn_point: (u8, u8)) -> Vec<Enemy> {
-    let config = WaveConfig::default();
-    let mut enemies = Vec::new();
+/// Pause between the end of one spawn group and the start of the next
+pub const GROUP_GAP_MICROS: u64 = 2_000_000;
+
+/// Wave script limits (keep scripts small enough to store on chain)
+pub const MAX_SCRIPT_NAME_LENGTH: usize = 50;
+pub const MAX_SCRIPT_WAVES: usize = 200;
+pub const MAX_GROUPS_PER_WAVE: usize = 20;
+pub const MAX_ENEMIES_PER_GROUP: u32 = 500;
+pub const MAX_HEALTH_PERCENT: u32 = 1000;
+
+/// A group of identical enemies released over time in bursts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnGroup {
+    pub enemy_type: EnemyType,
+    pub count: u32,
+    /// Delay from wave start until the first burst
+    pub start_delay_micros: u64,
+    /// Delay between consecutive bursts
+    pub interval_micros: u64,
+    /// Enemies released together in each burst
+    pub burst_size: u32,
+    /// Map lane the group spawns on and follows
+    pub lane: u8,
+    /// Health multiplier in percent (100 = unchanged)
+    pub health_percent: u32,
+}
+
+impl SpawnGroup {
+    /// Group with the default cadence for its enemy type, starting after `start_delay_micros`
+    pub fn new(enemy_type: EnemyType, count: u32, start_delay_micros: u64) -> Self {
+        let (interval_micros, burst_size) = match enemy_type {
+            EnemyType::BasicScout => (1_000_000, 1),
+            EnemyType::FastRunner => (2_500_000, 3), // Runners come in packs
+            EnemyType::HeavySoldier => (1_500_000, 1),
+            EnemyType::Tank => (3_000_000, 1),
+            EnemyType::Boss => (5_000_000, 1),
+            EnemyType::Healer => (3_000_000, 1),
+            EnemyType::ShieldBearer => (2_000_000, 1),
+            EnemyType::Splitter => (2_000_000, 1),
+            EnemyType::Splitling => (500_000, 3),
+            EnemyType::Brute => (3_000_000, 1),
+            EnemyType::Flyer => (2_000_000, 2), // Flyers come in pairs
+        };
+
+        Self {
+            enemy_type,
+            count,
+            start_delay_micros,
+            interval_micros,
+            burst_size,
+            lane: 0,
+            health_percent: 100,
+        }
+    }
 
+    /// Delay from wave start until the last enemy of the group spawns
+    pub fn end_delay_micros(&self) -> u64 {
+        let bursts = self.count.div_ceil(self.burst_size.max(1)) as u64;
+        self.start_delay_micros.saturating_add(
+            bursts
+                .saturating_sub(1)
+                .saturating_mul(self.interval_micros),
+        )
+    }
+}
+
+/// An enemy waiting to enter the map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSpawn {
+    pub enemy: Enemy,
+    /// Delay from wave start until the enemy spawns
+    pub delay_micros: u64,
+    pub lane: u8,
+}
+
+/// Procedural spawn groups for a wave, queued back to back
+pub fn wave_groups(wave_number: u32, config: &WaveConfig, rng: &mut GameRng) -> Vec<SpawnGroup> {
     // Calculate total enemy count for this wave
-    let enemy_count = config.base_enemy_count
+    let enemy_count = config
+        .base_enemy_count
         .saturating_add(wave_number.saturating_mul(config.enemy_count_per_wave));
 
     // Check if this is a boss wave
-    let is_boss_wave = wave_number % config.boss_wave_interval == 0;
-
-    let mut enemy_id = 0u64;
-
-    if is_boss_wave {
-        // Boss wave: 1 boss + supporting enemies
-        enemies.push(Enemy::new(
-            enemy_id,
-            EnemyType::Boss,
-            wave_number,
-            spawn_point,
-        ));
-        enemy_id += 1;
-
-        // Add supporting enemies (tanks and heavy soldiers)
-        let support_count = (enemy_count / 2).max(3);
-        for _ in 0..support_count {
-            enemies.push(Enemy::new(
-                enemy_id,
-                EnemyType::Tank,
-                wave_number,
-                spawn_point,
-            ));
-            enemy_id += 1;
-        }
+    let is_boss_wave =
+        config.boss_wave_interval > 0 && wave_number % config.boss_wave_interval == 0;
+
+    let composition: Vec<(EnemyType, u32)> = if is_boss_wave {
+        // Boss wave: 1 boss + supporting enemies (tanks and heavy soldiers)
+        vec![
+            (EnemyType::Boss, 1),
+            (EnemyType::Tank, (enemy_count / 2).max(3)),
+        ]
     } else {
         // Regular wave: mix of enemy types based on wave number
-        distribute_enemies(wave_number, enemy_count, spawn_point, &mut enemies, &mut enemy_id);
+        distribute_enemies(wave_number, enemy_count)
+    };
+
+    let mut groups = Vec::new();
+    let mut start_delay_micros = 0;
+    for (enemy_type, count) in composition {
+        if count == 0 {
+            continue;
+        }
+        let count = vary_count(count, config.variance_percent, rng);
+        let group = SpawnGroup::new(enemy_type, count, start_delay_micros);
+        start_delay_micros = group.end_delay_micros().saturating_add(GROUP_GAP_MICROS);
+        groups.push(group);
     }
+    groups
+}
 
-    enemies
+/// Scale a count by a random factor within +/- `variance_percent`, never dropping it to 0
+fn vary_count(count: u32, variance_percent: u32, rng: &mut GameRng) -> u32 {
+    let variance = variance_percent.min(100);
+    if variance == 0 {
+        return count;
+    }
+    let percent = 100 - variance + rng.below(variance * 2 + 1);
+    ((count as u64) * (percent as u64) / 100).clamp(1, u32::MAX as u64) as u32
 }
 
-fn distribute_enemies(
+/// Expand spawn groups into a schedule ordered by spawn time.
+/// Enemy IDs are assigned in spawn order starting at 0. Each group spawns at
+/// its lane's spawn point; lanes the map doesn't have fall back to lane 0.
+pub fn schedule_groups(
+    groups: &[SpawnGroup],
     wave_number: u32,
-    enemy_count: u32,
-    spawn_point: (u8, u8),
-    enemies: &mut Vec<Enemy>,
-    enemy_id: &mut u64,
-) {
-    // Wave difficulty progression affects enemy type distribution
-    match wave_number {
-        1..=3 => {
-            // Early waves: mostly basic scouts and some fast runners
-            for _ in 0..(enemy_count * 7 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::BasicScout, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::FastRunner, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
+    spawn_points: &[(u8, u8)],
+    difficulty: Difficulty,
+) -> Vec<ScheduledSpawn> {
+    let mut slots: Vec<(u64, &SpawnGroup)> = Vec::new();
+    for group in groups {
+        let burst_size = group.burst_size.max(1);
+        for index in 0..group.count {
+            let burst = (index / burst_size) as u64;
+            let delay = group
+                .start_delay_micros
+                .saturating_add(burst.saturating_mul(group.interval_micros));
+            slots.push((delay, group));
         }
-        4..=6 => {
-            // Mid-early waves: introduce heavy soldiers
-            for _ in 0..(enemy_count * 4 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::BasicScout, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::FastRunner, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::HeavySoldier, wave_number, spawn_point));
-                *enemy_id += 1;
+    }
+
+    // Stable sort keeps group order for enemies released at the same time
+    slots.sort_by_key(|(delay, _)| *delay);
+
+    slots
+        .into_iter()
+        .enumerate()
+        .map(|(id, (delay_micros, group))| {
+            let lane = if (group.lane as usize) < spawn_points.len() {
+                group.lane
+            } else {
+                0
+            };
+            let spawn_point = spawn_points.get(lane as usize).copied().unwrap_or_default();
+            let mut enemy = Enemy::with_difficulty(
+                id as u64,
+                group.enemy_type,
+                wave_number,
+                spawn_point,
+                difficulty,
+            );
+            enemy.lane = lane;
+            enemy.scale_health(group.health_percent);
+            ScheduledSpawn {
+                enemy,
+                delay_micros,
+                lane,
             }
+        })
+        .collect()
+}
+
+/// Generate the timed spawn schedule for a procedural wave with default settings
+pub fn generate_wave_schedule(
+    wave_number: u32,
+    spawn_point: (u8, u8),
+    rng: &mut GameRng,
+) -> Vec<ScheduledSpawn> {
+    let groups = wave_groups(wave_number, &WaveConfig::default(), rng);
+    schedule_groups(&groups, wave_number, &[spawn_point], Difficulty::Normal)
+}
+
+/// Generate every enemy of a wave in spawn order
+pub fn generate_wave(wave_number: u32, spawn_point: (u8, u8), rng: &mut GameRng) -> Vec<Enemy> {
+    generate_wave_schedule(wave_number, spawn_point, rng)
+        .into_iter()
+        .map(|spawn| spawn.enemy)
+        .collect()
+}
+
+/// Declarative campaign: one entry per wave, loaded from JSON.
+///
+/// ```json
+/// {
+///   "name": "Tutorial",
+///   "waves": [
+///     { "groups": [{ "enemy_type": "BasicScout", "count": 8, "interval_ms": 1000 }] },
+///     { "boss": true, "groups": [{ "enemy_type": "Boss", "count": 1, "health_percent": 50 }] }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveScript {
+    pub name: String,
+    pub waves: Vec<WaveDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveDefinition {
+    /// Boss waves must contain a boss group (and only they may)
+    #[serde(default)]
+    pub boss: bool,
+    pub groups: Vec<GroupDefinition>,
+    /// Combat mutators active for this wave only
+    #[serde(default)]
+    pub mutators: Vec<Mutator>,
+}
+
+/// One spawn group of a scripted wave; groups of a wave run in parallel,
+/// offset by their start delays
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupDefinition {
+    pub enemy_type: EnemyType,
+    pub count: u32,
+    #[serde(default = "default_health_percent")]
+    pub health_percent: u32,
+    #[serde(default)]
+    pub start_delay_ms: u64,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default = "default_burst_size")]
+    pub burst_size: u32,
+    #[serde(default)]
+    pub lane: u8,
+}
+
+fn default_health_percent() -> u32 {
+    100
+}
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+fn default_burst_size() -> u32 {
+    1
+}
+
+impl WaveScript {
+    /// Parse and validate a JSON wave script
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let script: Self =
+            serde_json::from_str(json).map_err(|e| format!("Invalid wave script: {}", e))?;
+        script.validate()?;
+        Ok(script)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Wave scripts always serialize")
+    }
+
+    /// Number of waves in the campaign (clearing the last one wins the game)
+    pub fn wave_count(&self) -> u32 {
+        self.waves.len() as u32
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.len() > MAX_SCRIPT_NAME_LENGTH {
+            return Err(format!(
+                "Script name must be 1-{} characters",
+                MAX_SCRIPT_NAME_LENGTH
+            ));
         }
-        7..=9 => {
-            // Mid waves: introduce tanks
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::BasicScout, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 2 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::FastRunner, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::HeavySoldier, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 2 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::Tank, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
+        if self.waves.is_empty() || self.waves.len() > MAX_SCRIPT_WAVES {
+            return Err(format!("Script must have 1-{} waves", MAX_SCRIPT_WAVES));
         }
-        _ => {
-            // Late waves: balanced mix with more tanks
-            for _ in 0..(enemy_count * 2 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::BasicScout, wave_number, spawn_point));
-                *enemy_id += 1;
-            }
-            for _ in 0..(enemy_count * 2 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::FastRunner, wave_number, spawn_point));
-                *enemy_id += 1;
+
+        for (index, wave) in self.waves.iter().enumerate() {
+            let wave_number = index + 1;
+            if wave.groups.is_empty() || wave.groups.len() > MAX_GROUPS_PER_WAVE {
+                return Err(format!(
+                    "Wave {} must have 1-{} groups",
+                    wave_number, MAX_GROUPS_PER_WAVE
+                ));
             }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::HeavySoldier, wave_number, spawn_point));
-                *enemy_id += 1;
+
+            validate_mutators(&wave.mutators, true)
+                .map_err(|e| format!("Wave {}: {}", wave_number, e))?;
+
+            let has_boss = wave.groups.iter().any(|g| g.enemy_type == EnemyType::Boss);
+            if wave.boss != has_boss {
+                return Err(format!(
+                    "Wave {}: boss waves must contain a Boss group and other waves must not",
+                    wave_number
+                ));
             }
-            for _ in 0..(enemy_count * 3 / 10) {
-                enemies.push(Enemy::new(*enemy_id, EnemyType::Tank, wave_number, spawn_point));
-                *enemy_id += 1;
+
+            for group in &wave.groups {
+                if group.count == 0 || group.count > MAX_ENEMIES_PER_GROUP {
+                    return Err(format!(
+                        "Wave {}: group count must be 1-{}",
+                        wave_number, MAX_ENEMIES_PER_GROUP
+                    ));
+                }
+                if group.health_percent == 0 || group.health_percent > MAX_HEALTH_PERCENT {
+                    return Err(format!(
+                        "Wave {}: health_percent must be 1-{}",
+                        wave_number, MAX_HEALTH_PERCENT
+                    ));
+                }
+                if group.burst_size == 0 {
+                    return Err(format!(
+                        "Wave {}: burst_size must be at least 1",
+                        wave_number
+                    ));
+                }
+                if group.interval_ms == 0 && group.count > group.burst_size {
+                    return Err(format!(
+                        "Wave {}: interval_ms must be positive for multi-burst groups",
+                        wave_number
+                    ));
+                }
             }
         }
+
+        Ok(())
+    }
+
+    /// Mutators a wave (1-based) adds on top of the game's
+    pub fn mutators(&self, wave_number: u32) -> &[Mutator] {
+        (wave_number as usize)
+            .checked_sub(1)
+            .and_then(|index| self.waves.get(index))
+            .map(|wave| wave.mutators.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Spawn groups for a wave (1-based); empty past the end of the campaign
+    pub fn groups(&self, wave_number: u32) -> Vec<SpawnGroup> {
+        let index = match (wave_number as usize).checked_sub(1) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+
+        self.waves
+            .get(index)
+            .map(|wave| {
+                wave.groups
+                    .iter()
+                    .map(|g| SpawnGroup {
+                        enemy_type: g.enemy_type,
+                        count: g.count,
+                        start_delay_micros: g.start_delay_ms.saturating_mul(1000),
+                        interval_micros: g.interval_ms.saturating_mul(1000),
+                        burst_size: g.burst_size,
+                        lane: g.lane,
+                        health_percent: g.health_percent,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn distribute_enemies(wave_number: u32, enemy_count: u32) -> Vec<(EnemyType, u32)> {
+    // Wave difficulty progression affects enemy type distribution
+    match wave_number {
+        1..=3 => vec![
+            // Early waves: mostly basic scouts and some fast runners
+            (EnemyType::BasicScout, enemy_count * 7 / 10),
+            (EnemyType::FastRunner, enemy_count * 3 / 10),
+        ],
+        4..=6 => vec![
+            // Mid-early waves: introduce heavy soldiers
+            (EnemyType::BasicScout, enemy_count * 4 / 10),
+            (EnemyType::FastRunner, enemy_count * 3 / 10),
+            (EnemyType::HeavySoldier, enemy_count * 3 / 10),
+        ],
+        7..=9 => vec![
+            // Mid waves: introduce tanks, with healers behind them
+            (EnemyType::BasicScout, enemy_count * 2 / 10),
+            (EnemyType::FastRunner, enemy_count * 2 / 10),
+            (EnemyType::HeavySoldier, enemy_count * 3 / 10),
+            (EnemyType::Tank, enemy_count * 2 / 10),
+            (EnemyType::Healer, enemy_count / 10),
+        ],
+        10..=14 => vec![
+            // Late waves: shields, splitters and flyers join the mix
+            (EnemyType::BasicScout, enemy_count / 10),
+            (EnemyType::Flyer, enemy_count / 10),
+            (EnemyType::FastRunner, enemy_count / 10),
+            (EnemyType::HeavySoldier, enemy_count * 2 / 10),
+            (EnemyType::ShieldBearer, enemy_count / 10),
+            (EnemyType::Tank, enemy_count * 2 / 10),
+            (EnemyType::Splitter, enemy_count / 10),
+            (EnemyType::Healer, enemy_count / 10),
+        ],
+        _ => vec![
+            // Endgame waves: regenerating brutes replace the scouts
+            (EnemyType::FastRunner, enemy_count / 10),
+            (EnemyType::Flyer, enemy_count / 10),
+            (EnemyType::HeavySoldier, enemy_count / 10),
+            (EnemyType::ShieldBearer, enemy_count / 10),
+            (EnemyType::Tank, enemy_count * 2 / 10),
+            (EnemyType::Brute, enemy_count * 2 / 10),
+            (EnemyType::Splitter, enemy_count / 10),
+            (EnemyType::Healer, enemy_count / 10),
+        ],
     }
 }
 
@@ -142,7 +443,7 @@ mod tests {
 
     #[test]
     fn test_wave_generation() {
-        let enemies = generate_wave(1, (0, 0));
+        let enemies = generate_wave(1, (0, 0), &mut GameRng::new(1));
         assert!(!enemies.is_empty());
 
         // Wave 1 should have base_enemy_count + 1 * enemy_count_per_wave = 10 + 2 = 12 enemies
@@ -151,9 +452,9 @@ mod tests {
 
     #[test]
     fn test_wave_scaling() {
-        let wave1 = generate_wave(1, (0, 0));
-        let wave5 = generate_wave(5, (0, 0));
-        let wave10 = generate_wave(10, (0, 0));
+        let wave1 = generate_wave(1, (0, 0), &mut GameRng::new(1));
+        let wave5 = generate_wave(5, (0, 0), &mut GameRng::new(1));
+        let wave10 = generate_wave(10, (0, 0), &mut GameRng::new(1));
 
         // Each wave should have more enemies
         assert!(wave5.len() > wave1.len());
@@ -162,7 +463,7 @@ mod tests {
 
     #[test]
     fn test_boss_wave() {
-        let wave10 = generate_wave(10, (0, 0));
+        let wave10 = generate_wave(10, (0, 0), &mut GameRng::new(1));
 
         // Wave 10 should be a boss wave
         let has_boss = wave10.iter().any(|e| e.enemy_type == EnemyType::Boss);
@@ -171,7 +472,7 @@ mod tests {
 
     #[test]
     fn test_non_boss_wave() {
-        let wave5 = generate_wave(5, (0, 0));
+        let wave5 = generate_wave(5, (0, 0), &mut GameRng::new(1));
 
         // Wave 5 should not have a boss
         let has_boss = wave5.iter().any(|e| e.enemy_type == EnemyType::Boss);
@@ -180,7 +481,7 @@ mod tests {
 
     #[test]
     fn test_early_wave_composition() {
-        let wave2 = generate_wave(2, (0, 0));
+        let wave2 = generate_wave(2, (0, 0), &mut GameRng::new(1));
 
         // Early waves should be mostly BasicScout and FastRunner
         let basic_count = wave2.iter().filter(|e| e.enemy_type == EnemyType::BasicScout).count();
@@ -196,7 +497,7 @@ mod tests {
 
     #[test]
     fn test_mid_wave_composition() {
-        let wave7 = generate_wave(7, (0, 0));
+        let wave7 = generate_wave(7, (0, 0), &mut GameRng::new(1));
 
         // Mid waves should have all enemy types except boss
         let has_basic = wave7.iter().any(|e| e.enemy_type == EnemyType::BasicScout);
@@ -207,9 +508,25 @@ mod tests {
         assert!(has_basic || has_fast || has_heavy || has_tank);
     }
 
+    #[test]
+    fn test_late_waves_use_ability_enemies() {
+        let wave8 = generate_wave(8, (0, 0), &mut GameRng::new(1));
+        assert!(wave8.iter().any(|e| e.enemy_type == EnemyType::Healer));
+
+        let wave12 = generate_wave(12, (0, 0), &mut GameRng::new(1));
+        assert!(wave12
+            .iter()
+            .any(|e| e.enemy_type == EnemyType::ShieldBearer));
+        assert!(wave12.iter().any(|e| e.enemy_type == EnemyType::Splitter));
+
+        let wave16 = generate_wave(16, (0, 0), &mut GameRng::new(1));
+        assert!(wave16.iter().any(|e| e.enemy_type == EnemyType::Brute));
+        assert!(!wave16.iter().any(|e| e.enemy_type == EnemyType::BasicScout));
+    }
+
     #[test]
     fn test_unique_enemy_ids() {
-        let wave = generate_wave(5, (0, 0));
+        let wave = generate_wave(5, (0, 0), &mut GameRng::new(1));
 
         // All enemy IDs should be unique
         let mut ids: Vec<u64> = wave.iter().map(|e| e.id).collect();
@@ -219,14 +536,163 @@ mod tests {
         assert_eq!(ids.len(), wave.len());
     }
 
+    #[test]
+    fn test_schedule_releases_enemies_over_time() {
+        let schedule = generate_wave_schedule(1, (0, 0), &mut GameRng::new(1));
+
+        assert_eq!(schedule[0].delay_micros, 0);
+        assert!(schedule
+            .windows(2)
+            .all(|w| w[0].delay_micros <= w[1].delay_micros));
+        assert!(schedule.last().unwrap().delay_micros > 0);
+    }
+
+    #[test]
+    fn test_spawn_group_bursts() {
+        let mut group = SpawnGroup::new(EnemyType::FastRunner, 7, 1_000_000);
+        group.interval_micros = 2_000_000;
+        group.burst_size = 3;
+
+        let schedule = schedule_groups(&[group.clone()], 1, &[(0, 0)], Difficulty::Normal);
+        let delays: Vec<u64> = schedule.iter().map(|s| s.delay_micros).collect();
+
+        assert_eq!(
+            delays,
+            vec![1_000_000, 1_000_000, 1_000_000, 3_000_000, 3_000_000, 3_000_000, 5_000_000]
+        );
+        assert_eq!(group.end_delay_micros(), 5_000_000);
+    }
+
+    #[test]
+    fn test_groups_run_back_to_back() {
+        let groups = wave_groups(5, &WaveConfig::default(), &mut GameRng::new(1));
+
+        for pair in groups.windows(2) {
+            assert_eq!(
+                pair[1].start_delay_micros,
+                pair[0].end_delay_micros() + GROUP_GAP_MICROS
+            );
+        }
+    }
+
+    #[test]
+    fn test_wave_config_is_used() {
+        let config = WaveConfig {
+            base_enemy_count: 20,
+            enemy_count_per_wave: 0,
+            boss_wave_interval: 3,
+            variance_percent: 0,
+        };
+
+        let mut rng = GameRng::new(1);
+        let total: u32 = wave_groups(1, &config, &mut rng)
+            .iter()
+            .map(|g| g.count)
+            .sum();
+        assert_eq!(total, 20);
+
+        let boss_wave = wave_groups(3, &config, &mut rng);
+        assert_eq!(boss_wave[0].enemy_type, EnemyType::Boss);
+    }
+
+    #[test]
+    fn test_wave_variance_is_seeded() {
+        let config = WaveConfig::default();
+        let counts = |seed| -> Vec<u32> {
+            wave_groups(12, &config, &mut GameRng::new(seed))
+                .iter()
+                .map(|g| g.count)
+                .collect()
+        };
+
+        assert_eq!(counts(5), counts(5));
+        assert!((0..20).any(|seed| counts(seed) != counts(5)));
+
+        // Every count stays within 20% of the unvaried composition
+        let exact = WaveConfig {
+            variance_percent: 0,
+            ..config.clone()
+        };
+        let base = wave_groups(12, &exact, &mut GameRng::new(0));
+        for (varied, base) in wave_groups(12, &config, &mut GameRng::new(9))
+            .iter()
+            .zip(&base)
+        {
+            assert_eq!(varied.enemy_type, base.enemy_type);
+            assert!(varied.count >= base.count * 80 / 100);
+            assert!(varied.count <= base.count * 120 / 100);
+        }
+    }
+
+    #[test]
+    fn test_parse_wave_script() {
+        let script = WaveScript::from_json(include_str!("../campaigns/tutorial.json")).unwrap();
+        assert_eq!(script.name, "Tutorial");
+        assert!(script.wave_count() > 0);
+
+        let groups = script.groups(1);
+        assert_eq!(groups[0].enemy_type, EnemyType::BasicScout);
+        assert_eq!(groups[0].health_percent, 100); // Defaulted
+        assert!(script.groups(0).is_empty());
+        assert!(script.groups(script.wave_count() + 1).is_empty());
+
+        // Round-trips through JSON
+        assert_eq!(WaveScript::from_json(&script.to_json()).unwrap(), script);
+    }
+
+    #[test]
+    fn test_scripted_health_multiplier() {
+        let script = WaveScript::from_json(
+            r#"{"name": "Hard", "waves": [{"groups": [
+                {"enemy_type": "BasicScout", "count": 2, "health_percent": 200, "start_delay_ms": 500}
+            ]}]}"#,
+        )
+        .unwrap();
+
+        let schedule = schedule_groups(&script.groups(1), 1, &[(0, 0)], Difficulty::Normal);
+        let base_health = EnemyType::BasicScout.base_stats(1).health;
+
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[0].delay_micros, 500_000);
+        assert_eq!(schedule[1].delay_micros, 1_500_000);
+        assert!(schedule
+            .iter()
+            .all(|s| s.enemy.max_health == base_health * 2));
+    }
+
+    #[test]
+    fn test_invalid_wave_scripts() {
+        // Malformed JSON and unknown enemy types
+        assert!(WaveScript::from_json("{").is_err());
+        assert!(WaveScript::from_json(
+            r#"{"name": "X", "waves": [{"groups": [{"enemy_type": "Dragon", "count": 1}]}]}"#
+        )
+        .is_err());
+
+        // No waves
+        assert!(WaveScript::from_json(r#"{"name": "X", "waves": []}"#).is_err());
+
+        // Boss flag without a boss
+        assert!(WaveScript::from_json(
+            r#"{"name": "X", "waves": [{"boss": true, "groups": [{"enemy_type": "Tank", "count": 1}]}]}"#
+        )
+        .is_err());
+
+        // Empty group
+        assert!(WaveScript::from_json(
+            r#"{"name": "X", "waves": [{"groups": [{"enemy_type": "Tank", "count": 0}]}]}"#
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_spawn_position() {
         let spawn = (5, 10);
-        let wave = generate_wave(3, spawn);
+        let wave = generate_wave(3, spawn, &mut GameRng::new(1));
 
         // All enemies should spawn at the correct position
         for enemy in wave {
-            assert_eq!(enemy.position, (spawn.0 as f32, spawn.1 as f32));
+            assert_eq!(enemy.position, (5000, 10000));
             assert_eq!(enemy.path_index, 0);
         }
     }
@@ -9,6 +9,7 @@ use linera_sdk::{
     views::{RootView, View},
     Contract, ContractRuntime,
 };
+use std::collections::{BTreeMap, HashSet};
 use tower_defense_abi::*;
 
 pub struct TowerDefenseContract {
@@ -50,16 +51,21 @@ impl Contract for TowerDefenseContract {
         self.state
             .unlocked_towers
             .set(vec![TowerType::Arrow, TowerType::Cannon]);
-        self.state.game_config.set(GameConfig::default());
+        let config = GameConfig::default();
+        self.state.base_health.set(config.base_health);
+        self.state.shared_gold.set(config.starting_gold);
+        self.state.game_config.set(config);
         self.state.game_status.set(GameStatus::Lobby);
         self.state.wave_number.set(0);
         self.state.wave_active.set(false);
-        self.state.base_health.set(20);
-        self.state.shared_gold.set(500);
         self.state.grid.set(Grid::new());
         self.state.room_info.set(RoomInfo::default());
         self.state.multiplayer_game.set(None);
         self.state.game_tick_count.set(0);
+        self.state.sim_time_micros.set(0);
+        self.state.pending_spawns.set(Vec::new());
+        self.state.wave_elapsed_micros.set(0);
+        self.state.next_enemy_id.set(0);
         self.state.last_wave_start_time.set(0);
         self.state.season.set(Season {
             season_id: 1,
@@ -84,9 +90,37 @@ impl Contract for TowerDefenseContract {
                 mode,
                 max_players,
                 is_private,
+                wave_script,
+                projectiles,
+                mazing,
+                map_id,
+                procedural_map,
+                difficulty,
+                mutators,
+                weekly_challenge,
             } => {
-                self.handle_create_game(owner, chain_id, mode, max_players, is_private)
-                    .await
+                let challenge_week =
+                    weekly_challenge.then(|| challenge_week(current_time.micros()));
+                let (settings, generated_map) = Self::parse_game_settings(
+                    wave_script,
+                    projectiles,
+                    mazing,
+                    map_id,
+                    procedural_map,
+                    difficulty,
+                    mutators,
+                    challenge_week,
+                );
+                self.handle_create_game(
+                    owner,
+                    chain_id,
+                    mode,
+                    max_players,
+                    is_private,
+                    settings,
+                    generated_map,
+                )
+                .await
             }
 
             Operation::JoinGame { game_id } => {
@@ -104,7 +138,7 @@ impl Contract for TowerDefenseContract {
                 position_y,
                 tower_type,
             } => {
-                self.handle_place_tower(owner, (position_x, position_y), tower_type, current_time)
+                self.handle_place_tower(owner, (position_x, position_y), tower_type)
                     .await
             }
 
@@ -112,14 +146,33 @@ impl Contract for TowerDefenseContract {
                 self.handle_upgrade_tower(owner, tower_id).await
             }
 
+            Operation::SpecializeTower {
+                tower_id,
+                specialization,
+            } => {
+                self.handle_specialize_tower(owner, tower_id, specialization)
+                    .await
+            }
+
             Operation::SellTower { tower_id } => self.handle_sell_tower(owner, tower_id).await,
 
+            Operation::SetTargetPriority { tower_id, priority } => {
+                self.handle_set_target_priority(owner, tower_id, priority)
+                    .await
+            }
+
             Operation::StartWave {} => self.handle_start_wave(current_time).await,
 
+            Operation::SubmitMap { map_id, map_json } => {
+                self.handle_submit_map(owner, map_id, map_json).await
+            }
+
             Operation::AddPublicChain {
                 public_chain_id,
                 region,
             } => self.handle_add_public_chain(public_chain_id, region).await,
+
+            Operation::SetFeaturedMaps { map_ids } => self.handle_set_featured_maps(map_ids).await,
         }
     }
 
@@ -139,6 +192,8 @@ impl Contract for TowerDefenseContract {
                 is_private,
                 player_name,
                 user_chain,
+                settings,
+                generated_map,
             } => {
                 self.handle_create_game_request(
                     mode,
@@ -146,6 +201,8 @@ impl Contract for TowerDefenseContract {
                     is_private,
                     player_name,
                     user_chain,
+                    settings,
+                    generated_map,
                 )
                 .await;
             }
@@ -248,6 +305,22 @@ impl Contract for TowerDefenseContract {
                 self.handle_report_score(scores).await;
             }
 
+            Message::SubmitMap {
+                map_id,
+                map,
+                author,
+            } => {
+                self.handle_submit_map_message(map_id, map, author).await;
+            }
+
+            Message::MapRequest { map_id, game_chain } => {
+                self.handle_map_request(map_id, game_chain).await;
+            }
+
+            Message::MapResponse { map_id, map } => {
+                self.handle_map_response(map_id, map).await;
+            }
+
             Message::SyncGameState { game_id } => {
                 self.handle_sync_game_state(game_id).await;
             }
@@ -262,6 +335,67 @@ impl Contract for TowerDefenseContract {
 impl TowerDefenseContract {
     // ===== Operation Handlers =====
 
+    /// Parse and validate the host's game settings before they leave this chain,
+    /// along with the procedural map's layout if one was asked for
+    fn parse_game_settings(
+        wave_script: Option<String>,
+        projectiles: bool,
+        mazing: bool,
+        map_id: Option<String>,
+        procedural_map: Option<ProceduralMap>,
+        difficulty: Difficulty,
+        mutators: Vec<Mutator>,
+        challenge_week: Option<u64>,
+    ) -> (GameSettings, Option<MapDefinition>) {
+        let wave_script = wave_script.map(|json| {
+            guards::validate_string_length(&json, guards::MAX_WAVE_SCRIPT_LENGTH, "wave_script")
+                .map_err(|e| panic!("{}", e))
+                .unwrap();
+            WaveScript::from_json(&json)
+                .map_err(|e| panic!("{}", e))
+                .unwrap()
+        });
+
+        if let Some(map_id) = &map_id {
+            validate_map_id(map_id)
+                .map_err(|e| panic!("{}", e))
+                .unwrap();
+        }
+        if procedural_map.is_some() && map_id.is_some() {
+            panic!("Choose a registry map or a procedural map, not both");
+        }
+        // Generating up front rejects seeds with no valid layout; the layout
+        // then travels with the request so it is only generated once
+        let generated_map = procedural_map.map(|procedural_map| {
+            procedural_map
+                .generate()
+                .map_err(|e| panic!("{}", e))
+                .unwrap()
+        });
+
+        // The challenge's mutators are fixed, so nobody can soften them
+        let mutators = match challenge_week {
+            Some(week) if mutators.is_empty() => challenge_mutators(week),
+            Some(_) => panic!("The weekly challenge picks its own mutators"),
+            None => mutators,
+        };
+        validate_mutators(&mutators, false)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        let settings = GameSettings {
+            wave_script,
+            projectiles,
+            mazing,
+            map_id,
+            procedural_map,
+            difficulty,
+            mutators,
+            challenge_week,
+        };
+        (settings, generated_map)
+    }
+
     async fn handle_create_game(
         &mut self,
         owner: AccountOwner,
@@ -269,7 +403,13 @@ impl TowerDefenseContract {
         mode: GameMode,
         max_players: u8,
         is_private: bool,
+        settings: GameSettings,
+        generated_map: Option<MapDefinition>,
     ) -> OperationResponse {
+        if mode == GameMode::Endless && settings.wave_script.is_some() {
+            panic!("Endless games use procedural waves");
+        }
+
         // Get player profile for name
         let profile = self.state.profile.get();
         let player_name = if profile.name.is_empty() {
@@ -288,6 +428,8 @@ impl TowerDefenseContract {
                 is_private,
                 player_name,
                 user_chain,
+                settings,
+                generated_map,
             },
         );
 
@@ -409,7 +551,6 @@ impl TowerDefenseContract {
         owner: AccountOwner,
         position: (u8, u8),
         tower_type: TowerType,
-        current_time: Timestamp,
     ) -> OperationResponse {
         // 1. Check tower limit FIRST (cheap operation before expensive state reads)
         guards::check_tower_limit(&self.state, owner)
@@ -419,17 +560,27 @@ impl TowerDefenseContract {
 
         // Get grid
         let grid = self.state.grid.get();
+        let config = self.state.game_config.get();
+        let mazing = config.mazing;
 
         // Validation (panic-based for atomic rollback)
         if !grid.is_valid_position(position) {
             panic!("Position out of bounds");
         }
 
-        if grid.is_on_path(position) {
+        // In mazing mode the path follows the towers, so only a full block is rejected
+        if !mazing && grid.is_on_path(position) {
             panic!("Cannot place tower on enemy path");
         }
 
+        // Rock, water and the map's own rules
+        grid.check_tower_placement(position, tower_type)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+        let terrain = grid.terrain_at(position);
+
         // Check if position is occupied
+        let mut blocked = HashSet::new();
         let tower_ids = self
             .state
             .towers
@@ -447,10 +598,17 @@ impl TowerDefenseContract {
             if tower.position == position {
                 panic!("Position already occupied");
             }
+            blocked.insert(Position::from_tuple(tower.position));
+        }
+
+        if mazing {
+            grid.check_maze_placement(position, &blocked)
+                .map_err(|e| panic!("{}", e))
+                .unwrap();
         }
 
         // Check gold
-        let cost = tower_type.cost();
+        let cost = mutated_cost(tower_type.cost(), &config.mutators);
         let mut gold = *self.state.shared_gold.get();
         if gold < cost {
             panic!("Insufficient gold");
@@ -473,7 +631,9 @@ impl TowerDefenseContract {
             .count()
             .await
             .expect("Failed to count towers");
-        let tower = Tower::new(tower_id as u64, position, tower_type, current_time.micros());
+        let sim_time = *self.state.sim_time_micros.get();
+        let mut tower = Tower::new(tower_id as u64, position, tower_type, sim_time);
+        tower.terrain = terrain;
 
         // Save tower
         self.state
@@ -502,6 +662,10 @@ impl TowerDefenseContract {
             .insert(&owner, stats)
             .expect("Failed to update player stats");
 
+        if mazing {
+            self.set_maze_tile(position, true).await;
+        }
+
         // Emit event
         self.emit_event(TowerDefenseEvent::TowerPlaced {
             tower_id: tower_id as u64,
@@ -534,11 +698,17 @@ impl TowerDefenseContract {
             .expect("Failed to get tower")
             .expect("Tower not found");
 
+        // Level 4 is a specialization branch, not a plain upgrade
+        if tower.level.saturating_add(1) >= SPECIALIZATION_LEVEL {
+            panic!("Tower is at max upgrade level; use SpecializeTower to pick a level-4 branch");
+        }
+
         // Check upgrade cost
         let cost = tower
             .tower_type
             .upgrade_cost(tower.level.saturating_add(1))
             .expect("Tower at max level or invalid level");
+        let cost = mutated_cost(cost, &self.state.game_config.get().mutators);
 
         // Check gold
         let mut gold = *self.state.shared_gold.get();
@@ -585,6 +755,73 @@ impl TowerDefenseContract {
         }
     }
 
+    async fn handle_specialize_tower(
+        &mut self,
+        owner: AccountOwner,
+        tower_id: u64,
+        specialization: Specialization,
+    ) -> OperationResponse {
+        guards::ensure_tower_owner(&self.state, tower_id, owner)
+            .await
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        let mut tower = self
+            .state
+            .towers
+            .get(&tower_id)
+            .await
+            .expect("Failed to get tower")
+            .expect("Tower not found");
+
+        // Validate the branch before charging for it
+        tower
+            .specialize(specialization)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        let cost = tower
+            .tower_type
+            .upgrade_cost(SPECIALIZATION_LEVEL)
+            .expect("Tower type has no specialization cost");
+        let cost = mutated_cost(cost, &self.state.game_config.get().mutators);
+
+        let mut gold = *self.state.shared_gold.get();
+        if gold < cost {
+            panic!("Insufficient gold for specialization");
+        }
+        gold = gold.saturating_sub(cost);
+        self.state.shared_gold.set(gold);
+
+        self.state
+            .towers
+            .insert(&tower_id, tower.clone())
+            .expect("Failed to update tower");
+
+        let mut stats = self
+            .state
+            .players
+            .get(&owner)
+            .await
+            .expect("Failed to get player stats")
+            .expect("Player stats not found");
+        stats.gold_spent = stats.gold_spent.saturating_add(cost);
+        self.state
+            .players
+            .insert(&owner, stats)
+            .expect("Failed to update player stats");
+
+        self.emit_event(TowerDefenseEvent::TowerSpecialized {
+            tower_id,
+            specialization,
+        });
+
+        OperationResponse::TowerUpgraded {
+            tower_id,
+            new_level: tower.level,
+        }
+    }
+
     async fn handle_sell_tower(&mut self, owner: AccountOwner, tower_id: u64) -> OperationResponse {
         // 1. Verify ownership FIRST (SECURITY FIX)
         guards::ensure_tower_owner(&self.state, tower_id, owner)
@@ -601,8 +838,13 @@ impl TowerDefenseContract {
             .expect("Failed to get tower")
             .expect("Tower not found");
 
-        // Calculate refund
-        let refund = tower.tower_type.sell_value(tower.level);
+        let mutators = &self.state.game_config.get().mutators;
+        if !selling_allowed(mutators) {
+            panic!("Selling is disabled in this game");
+        }
+
+        // Calculate refund (in step with what the tower cost under the mutators)
+        let refund = mutated_cost(tower.tower_type.sell_value(tower.level), mutators);
 
         // Add gold
         let mut gold = *self.state.shared_gold.get();
@@ -621,12 +863,48 @@ impl TowerDefenseContract {
             .remove(&tower_id)
             .expect("Failed to remove tower ownership");
 
+        // A freed tile may open a shorter route
+        if self.state.game_config.get().mazing {
+            self.set_maze_tile(tower.position, false).await;
+        }
+
         // Emit event
         self.emit_event(TowerDefenseEvent::TowerSold { tower_id, refund });
 
         OperationResponse::TowerSold { tower_id, refund }
     }
 
+    async fn handle_set_target_priority(
+        &mut self,
+        owner: AccountOwner,
+        tower_id: u64,
+        priority: TargetPriority,
+    ) -> OperationResponse {
+        guards::ensure_tower_owner(&self.state, tower_id, owner)
+            .await
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        let mut tower = self
+            .state
+            .towers
+            .get(&tower_id)
+            .await
+            .expect("Failed to get tower")
+            .expect("Tower not found");
+
+        tower.target_priority = priority;
+
+        self.state
+            .towers
+            .insert(&tower_id, tower)
+            .expect("Failed to update tower");
+
+        self.emit_event(TowerDefenseEvent::TargetPriorityChanged { tower_id, priority });
+
+        OperationResponse::TargetPrioritySet { tower_id }
+    }
+
     async fn handle_start_wave(&mut self, current_time: Timestamp) -> OperationResponse {
         // Validate state
         let wave_active = *self.state.wave_active.get();
@@ -638,8 +916,14 @@ impl TowerDefenseContract {
         let wave_number = *self.state.wave_number.get();
         let new_wave = wave_number.saturating_add(1);
 
-        // 1. Check wave limit (SECURITY FIX)
-        guards::validate_wave_number(new_wave)
+        // 1. Check wave limit (SECURITY FIX); endless games have none
+        let config = self.state.game_config.get();
+        if !config.endless {
+            guards::validate_wave_number(new_wave)
+                .map_err(|e| panic!("{}", e))
+                .unwrap();
+        }
+        guards::validate_campaign_wave(new_wave, config)
             .map_err(|e| panic!("{}", e))
             .unwrap();
 
@@ -655,23 +939,10 @@ impl TowerDefenseContract {
         // 4. Reset tick counter (SECURITY FIX)
         self.state.game_tick_count.set(0);
 
-        self.state.wave_number.set(new_wave);
-        self.state.wave_active.set(true);
-
-        // Generate enemies
-        let grid = self.state.grid.get();
-        let enemies = wave::generate_wave(new_wave, grid.spawn_point);
-
-        let enemy_count = enemies.len();
-
-        // Insert enemies
-        for enemy in enemies {
-            let id = enemy.id;
-            self.state
-                .enemies
-                .insert(&id, enemy)
-                .expect("Failed to insert enemy");
-        }
+        // Queue the wave's spawn schedule (released over time by game ticks)
+        let mut sim = self.load_simulation().await;
+        let enemy_count = sim.start_wave();
+        self.save_simulation(&sim).await;
 
         // Emit event
         self.emit_event(TowerDefenseEvent::WaveStarted {
@@ -719,6 +990,67 @@ impl TowerDefenseContract {
         OperationResponse::Ok
     }
 
+    async fn handle_submit_map(
+        &mut self,
+        owner: AccountOwner,
+        map_id: String,
+        map_json: String,
+    ) -> OperationResponse {
+        guards::validate_string_length(&map_json, guards::MAX_MAP_JSON_LENGTH, "map_json")
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+        validate_map_id(&map_id)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+        let map = MapDefinition::from_json(&map_json)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        // Maps submitted on the master chain itself are official
+        let params = self.runtime.application_parameters();
+        if guards::ensure_admin(self.runtime.chain_id(), &params).is_ok() {
+            if !self.register_map(map_id, map, owner, true).await {
+                panic!("Map ID already taken");
+            }
+        } else {
+            self.send_message(
+                params.master_chain,
+                Message::SubmitMap {
+                    map_id,
+                    map,
+                    author: owner,
+                },
+            );
+        }
+
+        OperationResponse::Ok
+    }
+
+    async fn handle_set_featured_maps(&mut self, map_ids: Vec<String>) -> OperationResponse {
+        let params = self.runtime.application_parameters();
+        guards::ensure_admin(self.runtime.chain_id(), &params)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+
+        if map_ids.len() > guards::MAX_FEATURED_MAPS {
+            panic!("Too many featured maps");
+        }
+        for map_id in &map_ids {
+            let registered = self
+                .state
+                .maps
+                .contains_key(map_id)
+                .await
+                .expect("Failed to check map");
+            if !registered {
+                panic!("Unknown map: {}", map_id);
+            }
+        }
+
+        self.state.featured_maps.set(map_ids);
+        OperationResponse::Ok
+    }
+
     // ===== Message Handlers =====
 
     async fn handle_find_game_request(&mut self, user_chain: ChainId) {
@@ -778,8 +1110,6 @@ impl TowerDefenseContract {
     }
 
     async fn handle_game_tick(&mut self, delta_time_micros: u64) {
-        use combat::*;
-
         // 1. Increment and check timeout FIRST (SECURITY FIX)
         let tick_count = *self.state.game_tick_count.get();
         if guards::should_timeout(tick_count) {
@@ -794,195 +1124,256 @@ impl TowerDefenseContract {
             return; // Already finalized, don't schedule more ticks
         }
 
-        let current_time = self.runtime.system_time().micros();
-
-        // Update enemies (movement)
-        let grid = self.state.grid.get();
-        let enemy_ids: Vec<u64> = self
-            .state
-            .enemies
-            .indices()
-            .await
-            .expect("Failed to get enemy indices");
-
-        for enemy_id in enemy_ids.clone() {
-            let mut enemy = match self
-                .state
-                .enemies
-                .get(&enemy_id)
-                .await
-                .expect("Failed to get enemy")
-            {
-                Some(e) => e,
-                None => continue,
-            };
-
-            // Move enemy
-            update_enemy_position(&mut enemy, &grid.path, delta_time_micros);
-
-            // Check if reached base
-            if enemy.path_index >= grid.path.len() {
-                let damage = enemy.damage_to_base;
-                let mut base_health = *self.state.base_health.get();
-                base_health = base_health.saturating_sub(damage);
-                self.state.base_health.set(base_health);
-
-                // Remove enemy
-                self.state
-                    .enemies
-                    .remove(&enemy_id)
-                    .expect("Failed to remove enemy");
+        // Run the shared simulation step and persist the result
+        let mut sim = self.load_simulation().await;
+        let events = sim.step(delta_time_micros);
+        self.save_simulation(&sim).await;
 
-                if base_health == 0 {
+        for event in events {
+            match event {
+                SimEvent::TowerFired {
+                    tower_id, damage, ..
+                }
+                | SimEvent::EnemyDamaged {
+                    tower_id, damage, ..
+                } => {
+                    self.credit_tower_owner(tower_id, damage as u64, false)
+                        .await;
+                }
+                SimEvent::EnemyKilled {
+                    enemy_id,
+                    tower_id,
+                    gold_reward,
+                } => {
+                    if let Some(owner) = self.credit_tower_owner(tower_id, 0, true).await {
+                        self.emit_event(TowerDefenseEvent::EnemyKilled {
+                            enemy_id,
+                            killed_by: owner,
+                            gold_reward,
+                        });
+                    }
+                }
+                SimEvent::BossPhaseChanged {
+                    enemy_id,
+                    phase,
+                    effects,
+                } => {
+                    self.emit_event(TowerDefenseEvent::BossPhaseChanged {
+                        enemy_id,
+                        phase,
+                        effects,
+                    });
+                }
+                SimEvent::EnemySpawned { .. }
+                | SimEvent::EnemyReachedBase { .. }
+                | SimEvent::EnemyHealed { .. }
+                | SimEvent::EnemySplit { .. }
+                | SimEvent::MinionSummoned { .. }
+                | SimEvent::TowerDisabled { .. }
+                | SimEvent::ProjectileLaunched { .. }
+                | SimEvent::ProjectileMissed { .. }
+                | SimEvent::CriticalHit { .. }
+                | SimEvent::EnemyDodged { .. }
+                | SimEvent::GoldGenerated { .. } => {}
+                SimEvent::BaseDestroyed => {
                     // Game over
                     self.finalize_game(false).await;
                     return;
                 }
-            } else {
-                // Update enemy position
-                self.state
-                    .enemies
-                    .insert(&enemy_id, enemy)
-                    .expect("Failed to update enemy");
+                SimEvent::WaveCleared {
+                    wave_number,
+                    bonus_gold,
+                } => {
+                    // 3. Victory condition check (SECURITY FIX); endless games only end in defeat
+                    let final_wave = sim.config.final_wave().unwrap_or(guards::MAX_WAVE_NUMBER);
+                    if !sim.config.endless && wave_number >= final_wave {
+                        // VICTORY! Completed every wave of the campaign
+                        self.finalize_game(true).await;
+                        return;
+                    }
+
+                    self.emit_event(TowerDefenseEvent::WaveCompleted {
+                        wave_number,
+                        bonus_gold,
+                    });
+                }
             }
         }
 
-        // Tower shooting logic
-        let tower_ids: Vec<u64> = self
+        if sim.wave_active {
+            // Schedule next tick
+            self.schedule_game_tick(100_000); // 100ms
+        }
+    }
+
+    /// Load the game chain state into a standalone simulation
+    async fn load_simulation(&self) -> Simulation {
+        let mut sim = Simulation::new(
+            self.state.grid.get().clone(),
+            *self.state.shared_gold.get(),
+            *self.state.base_health.get(),
+        );
+        sim.config = self.state.game_config.get().clone();
+        sim.wave_number = *self.state.wave_number.get();
+        sim.wave_active = *self.state.wave_active.get();
+        sim.time_micros = *self.state.sim_time_micros.get();
+        sim.pending_spawns = self.state.pending_spawns.get().clone();
+        sim.wave_elapsed_micros = *self.state.wave_elapsed_micros.get();
+        sim.next_enemy_id = *self.state.next_enemy_id.get();
+        sim.next_projectile_id = *self.state.next_projectile_id.get();
+        sim.rng = *self.state.rng.get();
+
+        let tower_ids = self
             .state
             .towers
             .indices()
             .await
             .expect("Failed to get tower indices");
-
         for tower_id in tower_ids {
-            let mut tower = match self
+            if let Some(tower) = self
                 .state
                 .towers
                 .get(&tower_id)
                 .await
                 .expect("Failed to get tower")
             {
-                Some(t) => t,
-                None => continue,
-            };
-
-            // Check if tower can fire
-            if !tower.can_fire(current_time) {
-                continue;
+                sim.add_tower(tower);
             }
+        }
 
-            // Find target
-            let enemy_ids: Vec<u64> = self
+        let enemy_ids = self
+            .state
+            .enemies
+            .indices()
+            .await
+            .expect("Failed to get enemy indices");
+        for enemy_id in enemy_ids {
+            if let Some(enemy) = self
                 .state
                 .enemies
-                .indices()
+                .get(&enemy_id)
                 .await
-                .expect("Failed to get enemy indices");
+                .expect("Failed to get enemy")
+            {
+                sim.add_enemy(enemy);
+            }
+        }
 
-            let mut enemies_with_ids = Vec::new();
-            for id in enemy_ids {
-                if let Some(enemy) = self
-                    .state
-                    .enemies
-                    .get(&id)
-                    .await
-                    .expect("Failed to get enemy")
-                {
-                    enemies_with_ids.push((id, enemy));
-                }
+        let projectile_ids = self
+            .state
+            .projectiles
+            .indices()
+            .await
+            .expect("Failed to get projectile indices");
+        for projectile_id in projectile_ids {
+            if let Some(projectile) = self
+                .state
+                .projectiles
+                .get(&projectile_id)
+                .await
+                .expect("Failed to get projectile")
+            {
+                sim.projectiles.push(projectile);
             }
+        }
 
-            if let Some(target_id) = find_target_id(&tower, &enemies_with_ids) {
-                // Fire at target
-                tower.last_shot_micros = current_time;
+        sim
+    }
 
-                let mut target = self
-                    .state
-                    .enemies
-                    .get(&target_id)
-                    .await
-                    .expect("Failed to get target")
-                    .expect("Target not found");
-
-                // Calculate and apply damage
-                let damage = calculate_damage(&tower, &target);
-                apply_tower_effects(&tower, &mut target);
-
-                let killed = target.take_damage(damage);
-
-                if killed {
-                    // Enemy killed
-                    let gold_reward = target.gold_reward;
-
-                    // Award gold
-                    let mut gold = *self.state.shared_gold.get();
-                    gold = gold.saturating_add(gold_reward);
-                    self.state.shared_gold.set(gold);
-
-                    // Remove enemy
-                    self.state
-                        .enemies
-                        .remove(&target_id)
-                        .expect("Failed to remove enemy");
-
-                    // Emit event (would need owner tracking)
-                } else {
-                    // Update enemy
-                    self.state
-                        .enemies
-                        .insert(&target_id, target)
-                        .expect("Failed to update enemy");
-                }
+    /// Block or free a tile of the mazing flow field (panics if it would trap enemies)
+    async fn set_maze_tile(&mut self, tile: (u8, u8), blocked: bool) {
+        let mut sim = self.load_simulation().await;
+        sim.set_tile_blocked(tile, blocked)
+            .map_err(|e| panic!("{}", e))
+            .unwrap();
+        self.save_simulation(&sim).await;
+    }
 
-                // Update tower stats
-                tower.record_damage(damage as u64);
-                self.state
-                    .towers
-                    .insert(&tower_id, tower)
-                    .expect("Failed to update tower");
-            }
+    /// Persist a simulation back into the game chain state
+    async fn save_simulation(&mut self, sim: &Simulation) {
+        self.state.grid.set(sim.grid.clone());
+        self.state.shared_gold.set(sim.gold);
+        self.state.base_health.set(sim.base_health);
+        self.state.wave_number.set(sim.wave_number);
+        self.state.wave_active.set(sim.wave_active);
+        self.state.sim_time_micros.set(sim.time_micros);
+        self.state.pending_spawns.set(sim.pending_spawns.clone());
+        self.state.wave_elapsed_micros.set(sim.wave_elapsed_micros);
+        self.state.next_enemy_id.set(sim.next_enemy_id);
+        self.state.next_projectile_id.set(sim.next_projectile_id);
+        self.state.rng.set(sim.rng);
+
+        for tower in &sim.towers {
+            self.state
+                .towers
+                .insert(&tower.id, tower.clone())
+                .expect("Failed to update tower");
         }
 
-        // Check wave completion
-        let enemy_count = self
+        // Remove enemies that were killed or reached the base
+        let stored_enemy_ids = self
             .state
             .enemies
-            .count()
+            .indices()
             .await
-            .expect("Failed to count enemies");
-
-        if enemy_count == 0 {
-            // 3. Victory condition check (SECURITY FIX)
-            let wave_number = *self.state.wave_number.get();
-            if wave_number >= guards::MAX_WAVE_NUMBER {
-                // VICTORY! Completed all 100 waves
-                self.finalize_game(true).await;
-                return;
+            .expect("Failed to get enemy indices");
+        for enemy_id in stored_enemy_ids {
+            if !sim.enemies.iter().any(|e| e.id == enemy_id) {
+                self.state
+                    .enemies
+                    .remove(&enemy_id)
+                    .expect("Failed to remove enemy");
             }
+        }
+        for enemy in &sim.enemies {
+            self.state
+                .enemies
+                .insert(&enemy.id, enemy.clone())
+                .expect("Failed to update enemy");
+        }
 
-            self.complete_wave().await;
-        } else {
-            // Schedule next tick
-            self.schedule_game_tick(100_000); // 100ms
+        // Projectiles only live until they land, so rewrite the whole set
+        self.state.projectiles.clear();
+        for projectile in &sim.projectiles {
+            self.state
+                .projectiles
+                .insert(&projectile.id, projectile.clone())
+                .expect("Failed to update projectile");
         }
     }
 
-    async fn complete_wave(&mut self) {
-        self.state.wave_active.set(false);
-
-        // Award gold
-        let wave_number = *self.state.wave_number.get();
-        let bonus = 50u64.saturating_add(wave_number.saturating_mul(10) as u64);
+    /// Attribute tower damage and kills to the tower's owner
+    async fn credit_tower_owner(
+        &mut self,
+        tower_id: u64,
+        damage: u64,
+        killed: bool,
+    ) -> Option<AccountOwner> {
+        let owner = self
+            .state
+            .tower_owners
+            .get(&tower_id)
+            .await
+            .expect("Failed to get tower owner")?;
 
-        let mut gold = *self.state.shared_gold.get();
-        gold = gold.saturating_add(bonus);
-        self.state.shared_gold.set(gold);
+        if let Some(mut stats) = self
+            .state
+            .players
+            .get(&owner)
+            .await
+            .expect("Failed to get player stats")
+        {
+            stats.damage_dealt = stats.damage_dealt.saturating_add(damage);
+            if killed {
+                stats.kills = stats.kills.saturating_add(1);
+            }
+            self.state
+                .players
+                .insert(&owner, stats)
+                .expect("Failed to update player stats");
+        }
 
-        self.emit_event(TowerDefenseEvent::WaveCompleted {
-            wave_number,
-            bonus_gold: bonus,
-        });
+        Some(owner)
     }
 
     async fn finalize_game(&mut self, victory: bool) {
@@ -994,12 +1385,26 @@ impl TowerDefenseContract {
         self.state.game_status.set(status);
 
         let wave_number = *self.state.wave_number.get();
+        let seed = *self.state.game_seed.get();
+        let config = self.state.game_config.get();
+        let endless = config.endless;
+        let difficulty = config.difficulty;
+        let mutators = config.mutators.clone();
+        let challenge_week = config.challenge_week;
+        // A lost wave doesn't count as survived
+        let waves_survived = if victory {
+            wave_number
+        } else {
+            wave_number.saturating_sub(1)
+        };
 
         // Emit game over event
         self.emit_event(TowerDefenseEvent::GameOver {
             victory,
             final_wave: wave_number,
             winner: None, // Would need to determine winner in multiplayer
+            seed,
+            mutators: mutators.clone(),
         });
 
         // Collect player scores
@@ -1026,6 +1431,12 @@ impl TowerDefenseContract {
                 wave_reached: wave_number,
                 kills: stats.kills,
                 damage_dealt: stats.damage_dealt,
+                seed,
+                endless,
+                waves_survived,
+                difficulty,
+                mutators: mutators.clone(),
+                challenge_week,
             });
         }
 
@@ -1047,15 +1458,32 @@ impl TowerDefenseContract {
                     player: score.owner,
                     chain_id: score.chain_id,
                     highest_wave: 0,
+                    highest_endless_wave: 0,
+                    highest_wave_by_difficulty: BTreeMap::new(),
+                    highest_endless_wave_by_difficulty: BTreeMap::new(),
                     total_kills: 0,
                     total_games: 0,
                     total_damage: 0,
                     last_updated: 0,
                 });
 
-            // Update stats
-            if score.wave_reached > entry.highest_wave {
-                entry.highest_wave = score.wave_reached;
+            // Update stats; endless runs rank on their own board
+            if score.endless {
+                entry.highest_endless_wave = entry.highest_endless_wave.max(score.waves_survived);
+                let best = entry
+                    .highest_endless_wave_by_difficulty
+                    .entry(score.difficulty)
+                    .or_insert(0);
+                *best = (*best).max(score.waves_survived);
+            } else {
+                if score.wave_reached > entry.highest_wave {
+                    entry.highest_wave = score.wave_reached;
+                }
+                let best = entry
+                    .highest_wave_by_difficulty
+                    .entry(score.difficulty)
+                    .or_insert(0);
+                *best = (*best).max(score.wave_reached);
             }
             entry.total_kills = entry.total_kills.saturating_add(score.kills as u64);
             entry.total_games = entry.total_games.saturating_add(1);
@@ -1069,6 +1497,58 @@ impl TowerDefenseContract {
         }
     }
 
+    async fn handle_submit_map_message(
+        &mut self,
+        map_id: String,
+        map: MapDefinition,
+        author: AccountOwner,
+    ) {
+        // This runs on master chain; re-check what the submitting chain claimed
+        if validate_map_id(&map_id).is_err() || map.validate().is_err() {
+            return;
+        }
+        // First submission wins an ID
+        self.register_map(map_id, map, author, false).await;
+    }
+
+    async fn handle_map_request(&mut self, map_id: String, game_chain: ChainId) {
+        // This runs on master chain
+        let map = self
+            .state
+            .maps
+            .get(&map_id)
+            .await
+            .expect("Failed to get map")
+            .map(|entry| entry.map);
+
+        self.send_message(game_chain, Message::MapResponse { map_id, map });
+    }
+
+    async fn handle_map_response(&mut self, map_id: String, map: Option<MapDefinition>) {
+        // This runs on game chain; the lobby opens once its map is here.
+        // Only the master chain's registry may answer.
+        let params = self.runtime.application_parameters();
+        if self.runtime.message_origin_chain_id() != Some(params.master_chain) {
+            return;
+        }
+        let pending = match self.state.pending_game.get().clone() {
+            Some(pending) if pending.settings.map_id.as_ref() == Some(&map_id) => pending,
+            _ => return,
+        };
+        self.state.pending_game.set(None);
+
+        match map.filter(|map| map.validate().is_ok()) {
+            Some(map) => self.open_lobby(pending, map.to_grid()).await,
+            None => self.send_message(
+                pending.user_chain,
+                Message::CreateGameResult {
+                    game_id: String::new(),
+                    success: false,
+                },
+            ),
+        }
+    }
+
     // ===== Multiplayer Message Handlers =====
 
     async fn handle_create_game_request(
@@ -1078,8 +1558,77 @@ impl TowerDefenseContract {
         is_private: bool,
         player_name: String,
         user_chain: ChainId,
+        settings: GameSettings,
+        generated_map: Option<MapDefinition>,
     ) {
-        // This runs on game chain
+        // This runs on game chain. One create at a time: a second one would
+        // overwrite the request still waiting for its map.
+        if self.state.pending_game.get().is_some() {
+            self.send_message(
+                user_chain,
+                Message::CreateGameResult {
+                    game_id: String::new(),
+                    success: false,
+                },
+            );
+            return;
+        }
+        let request = PendingGame {
+            mode,
+            max_players,
+            is_private,
+            player_name,
+            user_chain,
+            settings,
+        };
+
+        // Registry maps live on the master chain; wait for ours before opening the lobby
+        if let Some(map_id) = request.settings.map_id.clone() {
+            self.state.pending_game.set(Some(request));
+            let params = self.runtime.application_parameters();
+            let game_chain = self.runtime.chain_id();
+            self.send_message(
+                params.master_chain,
+                Message::MapRequest { map_id, game_chain },
+            );
+            return;
+        }
+
+        let grid = match generated_map {
+            Some(map) => map.to_grid(),
+            None => Grid::new(),
+        };
+        self.open_lobby(request, grid).await;
+    }
+
+    /// Set up the game on `grid` and tell the host their lobby is open
+    async fn open_lobby(&mut self, request: PendingGame, mut grid: Grid) {
+        let PendingGame {
+            mode,
+            max_players,
+            is_private,
+            player_name,
+            user_chain,
+            settings,
+        } = request;
+
+        // Apply per-game settings
+        let mut config = self.state.game_config.get().clone();
+        settings.apply_to(&mut config);
+        config.endless = mode == GameMode::Endless;
+        if config.mazing {
+            grid.build_flow_field()
+                .map_err(|e| panic!("{}", e))
+                .unwrap();
+        }
+        config.grid_width = grid.width;
+        config.grid_height = grid.height;
+        // Lives and gold come from the difficulty preset
+        self.state.base_health.set(config.base_health);
+        self.state.shared_gold.set(config.starting_gold);
+        self.state.grid.set(grid);
+        self.state.game_config.set(config);
+
         // Generate unique game ID
         let game_id = format!(
             "game_{}_{:?}",
@@ -1087,6 +1636,11 @@ impl TowerDefenseContract {
             self.runtime.chain_id()
         );
 
+        // Every random roll in this game derives from its ID
+        let seed = GameRng::seed_from_bytes(game_id.as_bytes());
+        self.state.game_seed.set(seed);
+        self.state.rng.set(GameRng::new(seed));
+
         // Get player owner from authenticated signer (would need proper handling)
         let owner = AccountOwner::from([0u8; 32]); // Placeholder
 
@@ -1424,6 +1978,37 @@ impl TowerDefenseContract {
 
     // ===== Helper Methods =====
 
+    /// Store a validated map under a free ID; returns false if the ID is taken
+    async fn register_map(
+        &mut self,
+        map_id: String,
+        map: MapDefinition,
+        author: AccountOwner,
+        official: bool,
+    ) -> bool {
+        let taken = self
+            .state
+            .maps
+            .contains_key(&map_id)
+            .await
+            .expect("Failed to check map");
+        if taken {
+            return false;
+        }
+
+        let entry = MapEntry {
+            map,
+            author,
+            official,
+            submitted_at: self.runtime.system_time().micros(),
+        };
+        self.state
+            .maps
+            .insert(&map_id, entry)
+            .expect("Failed to insert map");
+        true
+    }
+
     fn send_message(&mut self, destination: ChainId, message: Message) {
         self.runtime
             .prepare_message(message)
@@ -1,10 +1,16 @@
 use crate::{state::TowerDefenseState, TowerDefenseParameters};
 /// Security guards and validation utilities
 use linera_sdk::linera_base_types::{AccountOwner, ChainId};
+use tower_defense_abi::GameConfig;
 
 /// Maximum string lengths to prevent state bloat
 pub const MAX_REGION_LENGTH: usize = 100;
 pub const MAX_PLAYER_NAME_LENGTH: usize = 50;
+pub const MAX_WAVE_SCRIPT_LENGTH: usize = 64 * 1024;
+pub const MAX_MAP_JSON_LENGTH: usize = 64 * 1024;
+
+/// Map registry limits
+pub const MAX_FEATURED_MAPS: usize = 52;
 
 /// Game limits
 pub const MAX_TOWERS_PER_PLAYER: usize = 20;
@@ -100,6 +106,14 @@ pub fn validate_wave_number(wave: u32) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Validate wave number against a scripted campaign's length
+pub fn validate_campaign_wave(wave: u32, config: &GameConfig) -> Result<(), &'static str> {
+    match config.final_wave() {
+        Some(final_wave) if wave > final_wave => Err("Campaign complete. Victory!"),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
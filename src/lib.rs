@@ -32,6 +32,22 @@ pub enum Operation {
         mode: GameMode,
         max_players: u8,
         is_private: bool,
+        /// Optional JSON wave script replacing procedural waves
+        wave_script: Option<String>,
+        /// Tower shots travel as projectiles instead of hitting instantly
+        projectiles: bool,
+        /// Open field where enemies path around towers instead of a fixed path
+        mazing: bool,
+        /// Registry map to play on (the classic map when unset)
+        map_id: Option<String>,
+        /// Seeded procedural map to play on instead
+        procedural_map: Option<ProceduralMap>,
+        /// Preset for enemy health, kill rewards, starting gold and lives
+        difficulty: Difficulty,
+        /// Rule changes for the whole game
+        mutators: Vec<Mutator>,
+        /// Play this week's challenge; its mutators are picked on chain
+        weekly_challenge: bool,
     },
 
     /// Join an existing game
@@ -53,18 +69,36 @@ pub enum Operation {
     /// Upgrade an existing tower
     UpgradeTower { tower_id: u64 },
 
+    /// Upgrade a level-3 tower to level 4 along the named branch
+    SpecializeTower {
+        tower_id: u64,
+        specialization: Specialization,
+    },
+
     /// Sell a tower for gold
     SellTower { tower_id: u64 },
 
+    /// Choose which enemy in range a tower shoots at
+    SetTargetPriority {
+        tower_id: u64,
+        priority: TargetPriority,
+    },
+
     /// Start the next wave
     StartWave {},
 
+    /// Submit a JSON map to the master chain's registry under a new ID
+    SubmitMap { map_id: String, map_json: String },
+
     // ===== Master Operations =====
     /// Add a new public chain to the registry (master only)
     AddPublicChain {
         public_chain_id: ChainId,
         region: String,
     },
+
+    /// Set the maps that take turns as the weekly featured map (master only)
+    SetFeaturedMaps { map_ids: Vec<String> },
 }
 
 /// Responses from operations
@@ -79,6 +113,7 @@ pub enum OperationResponse {
     TowerPlaced { tower_id: u64 },
     TowerUpgraded { tower_id: u64, new_level: u8 },
     TowerSold { tower_id: u64, refund: u64 },
+    TargetPrioritySet { tower_id: u64 },
     WaveStarted { wave_number: u32 },
 }
 
@@ -99,6 +134,9 @@ pub enum Message {
         is_private: bool,
         player_name: String,
         user_chain: ChainId,
+        settings: GameSettings,
+        /// Layout of `settings.procedural_map`, generated on the host's chain
+        generated_map: Option<MapDefinition>,
     },
 
     /// Create game response (game chain -> user chain)
@@ -179,6 +217,23 @@ pub enum Message {
     /// Report final scores to master chain (game chain -> master chain)
     ReportScore { scores: Vec<PlayerScore> },
 
+    // ===== Map Registry Messages =====
+    /// Community map submission (user chain -> master chain)
+    SubmitMap {
+        map_id: String,
+        map: MapDefinition,
+        author: AccountOwner,
+    },
+
+    /// Fetch a registered map before opening a lobby (game chain -> master chain)
+    MapRequest { map_id: String, game_chain: ChainId },
+
+    /// The requested map, or None if no map has that ID (master chain -> game chain)
+    MapResponse {
+        map_id: String,
+        map: Option<MapDefinition>,
+    },
+
     // ===== State Sync Messages =====
     /// Full game state sync (game chain -> player chain)
     SyncGameState { game_id: String },
@@ -228,11 +283,21 @@ pub enum TowerDefenseEvent {
         new_level: u8,
     },
 
+    TowerSpecialized {
+        tower_id: u64,
+        specialization: Specialization,
+    },
+
     TowerSold {
         tower_id: u64,
         refund: u64,
     },
 
+    TargetPriorityChanged {
+        tower_id: u64,
+        priority: TargetPriority,
+    },
+
     EnemyKilled {
         enemy_id: u64,
         killed_by: AccountOwner,
@@ -250,6 +315,12 @@ pub enum TowerDefenseEvent {
         bonus_gold: u64,
     },
 
+    BossPhaseChanged {
+        enemy_id: u64,
+        phase: u8,
+        effects: Vec<PhaseEffect>,
+    },
+
     PlayerHealthChanged {
         game_id: String,
         player_id: AccountOwner,
@@ -266,6 +337,9 @@ pub enum TowerDefenseEvent {
         victory: bool,
         final_wave: u32,
         winner: Option<AccountOwner>,
+        seed: u64,
+        /// Game-wide mutators the game was played with
+        mutators: Vec<Mutator>,
     },
 
     GameEnded {
@@ -7,7 +7,7 @@ use linera_sdk::{
     views::View,
     Service, ServiceRuntime,
 };
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use tower_defense_abi::*;
 
 pub struct TowerDefenseService {
@@ -71,6 +71,11 @@ impl QueryRoot {
         *self.state.wave_number.get() as i32
     }
 
+    /// Seed of this game's random rolls, for replaying it exactly
+    async fn game_seed(&self) -> String {
+        self.state.game_seed.get().to_string()
+    }
+
     async fn wave_active(&self) -> bool {
         *self.state.wave_active.get()
     }
@@ -85,16 +90,42 @@ impl QueryRoot {
 
     async fn grid(&self) -> GridData {
         let grid = self.state.grid.get();
+        let first_lane = LaneData::from(grid.lane(0));
         GridData {
             width: grid.width as i32,
             height: grid.height as i32,
-            path: grid
-                .path
+            path: first_lane.path.clone(),
+            air_path: first_lane.air_path.clone(),
+            spawn_point: first_lane.spawn_point.clone(),
+            lanes: grid.lanes.iter().map(LaneData::from).collect(),
+            base_point: vec![grid.base_point.0 as i32, grid.base_point.1 as i32],
+            terrain: grid
+                .terrain
                 .iter()
-                .map(|(x, y)| vec![*x as i32, *y as i32])
+                .map(|((x, y), terrain)| TerrainTileData {
+                    position: vec![*x as i32, *y as i32],
+                    terrain: format!("{:?}", terrain),
+                    speed: milli_to_f64(terrain.speed_permille() as i64),
+                    buildable: terrain.is_buildable(),
+                    allowed_towers: TowerType::ALL
+                        .iter()
+                        .filter(|tower_type| terrain.allows_tower(**tower_type))
+                        .map(|tower_type| format!("{:?}", tower_type))
+                        .collect(),
+                    range_bonus: terrain.range_bonus() as i32,
+                    damage_bonus_percent: terrain.damage_bonus_percent() as i32,
+                })
+                .collect(),
+            unbuildable: tiles_data(&grid.unbuildable),
+            build_pads: grid.build_pads.as_ref().map(tiles_data),
+            decorations: grid
+                .decorations
+                .iter()
+                .map(|decoration| DecorationData {
+                    kind: decoration.kind.clone(),
+                    position: tile_data(decoration.tile),
+                })
                 .collect(),
-            spawn_point: vec![grid.spawn_point.0 as i32, grid.spawn_point.1 as i32],
-            base_point: vec![grid.base_point.0 as i32, grid.base_point.1 as i32],
         }
     }
 
@@ -106,43 +137,44 @@ impl QueryRoot {
             starting_gold: config.starting_gold.to_string(),
             base_health: config.base_health as i32,
             max_towers: config.max_towers as i32,
+            wave_script_name: config
+                .wave_script
+                .as_ref()
+                .map(|script| script.name.clone()),
+            final_wave: config.final_wave().map(|wave| wave as i32),
+            projectiles: config.projectiles,
+            mazing: config.mazing,
+            map_id: config.map_id.clone(),
+            map_seed: config
+                .procedural_map
+                .map(|procedural_map| procedural_map.seed.to_string()),
+            difficulty: format!("{:?}", config.difficulty),
+            mutators: config
+                .mutators
+                .iter()
+                .map(|mutator| format!("{:?}", mutator))
+                .collect(),
+            challenge_week: config.challenge_week.map(|week| week.to_string()),
         }
     }
 
     // ===== Tower Queries =====
 
     async fn towers(&self) -> Vec<TowerData> {
-        let indices = self
-            .state
-            .towers
-            .indices()
+        self.load_towers()
             .await
-            .expect("Failed to get tower indices");
-
-        let mut towers = Vec::new();
-        for id in indices {
-            if let Some(tower) = self
-                .state
-                .towers
-                .get(&id)
-                .await
-                .expect("Failed to get tower")
-            {
-                towers.push(TowerData::from(tower));
-            }
-        }
-        towers
+            .into_iter()
+            .map(TowerData::from)
+            .collect()
     }
 
     async fn tower(&self, tower_id: String) -> Option<TowerData> {
         let id = tower_id.parse::<u64>().ok()?;
-        let tower = self
-            .state
-            .towers
-            .get(&id)
+        self.load_towers()
             .await
-            .expect("Failed to get tower")?;
-        Some(TowerData::from(tower))
+            .into_iter()
+            .find(|tower| tower.id == id)
+            .map(TowerData::from)
     }
 
     async fn tower_count(&self) -> i32 {
@@ -189,6 +221,30 @@ impl QueryRoot {
         Some(EnemyData::from(enemy))
     }
 
+    /// Tower shots currently in flight
+    async fn projectiles(&self) -> Vec<ProjectileData> {
+        let indices = self
+            .state
+            .projectiles
+            .indices()
+            .await
+            .expect("Failed to get projectile indices");
+
+        let mut projectiles = Vec::new();
+        for id in indices {
+            if let Some(projectile) = self
+                .state
+                .projectiles
+                .get(&id)
+                .await
+                .expect("Failed to get projectile")
+            {
+                projectiles.push(ProjectileData::from(projectile));
+            }
+        }
+        projectiles
+    }
+
     async fn enemy_count(&self) -> i32 {
         self.state
             .enemies
@@ -197,6 +253,10 @@ impl QueryRoot {
             .expect("Failed to count enemies") as i32
     }
 
+    async fn pending_spawn_count(&self) -> i32 {
+        self.state.pending_spawns.get().len() as i32
+    }
+
     // ===== Player Queries =====
 
     async fn players(&self) -> Vec<PlayerData> {
@@ -298,6 +358,89 @@ impl QueryRoot {
         entries
     }
 
+    /// Endless mode board: players ranked by most waves survived in one endless game
+    async fn endless_leaderboard(&self, limit: Option<i32>) -> Vec<LeaderboardEntryData> {
+        let limit = limit.unwrap_or(100).min(100) as usize;
+        let indices = self
+            .state
+            .leaderboard
+            .indices()
+            .await
+            .expect("Failed to get leaderboard indices");
+
+        let mut entries = Vec::new();
+        for owner in indices {
+            if let Some(entry) = self
+                .state
+                .leaderboard
+                .get(&owner)
+                .await
+                .expect("Failed to get leaderboard entry")
+            {
+                if entry.highest_endless_wave > 0 {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| b.highest_endless_wave.cmp(&a.highest_endless_wave));
+        entries
+            .into_iter()
+            .take(limit)
+            .map(LeaderboardEntryData::from)
+            .collect()
+    }
+
+    /// Players ranked by highest wave reached on one difficulty ("Easy", "Normal", ...);
+    /// with `endless`, by most waves survived in one endless game on it
+    async fn difficulty_leaderboard(
+        &self,
+        difficulty: String,
+        endless: Option<bool>,
+        limit: Option<i32>,
+    ) -> Vec<LeaderboardEntryData> {
+        let limit = limit.unwrap_or(100).min(100) as usize;
+        let Some(difficulty) = Difficulty::ALL
+            .into_iter()
+            .find(|preset| format!("{:?}", preset) == difficulty)
+        else {
+            return Vec::new();
+        };
+        let indices = self
+            .state
+            .leaderboard
+            .indices()
+            .await
+            .expect("Failed to get leaderboard indices");
+
+        let mut entries = Vec::new();
+        for owner in indices {
+            if let Some(entry) = self
+                .state
+                .leaderboard
+                .get(&owner)
+                .await
+                .expect("Failed to get leaderboard entry")
+            {
+                let waves = if endless.unwrap_or(false) {
+                    &entry.highest_endless_wave_by_difficulty
+                } else {
+                    &entry.highest_wave_by_difficulty
+                };
+                if let Some(wave) = waves.get(&difficulty).copied() {
+                    entries.push((wave, entry));
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        entries
+            .into_iter()
+            .take(limit)
+            .map(|(_, entry)| LeaderboardEntryData::from(entry))
+            .collect()
+    }
+
     async fn leaderboard_entry(&self, owner: String) -> Option<LeaderboardEntryData> {
         // TODO: AccountOwner changed to an enum (Ed25519PublicKey, etc)
         // Leaderboard lookup by hex string needs reimplementation
@@ -314,6 +457,51 @@ impl QueryRoot {
         }
     }
 
+    // ===== Map Registry Queries =====
+
+    async fn maps(&self, limit: Option<i32>) -> Vec<MapEntryData> {
+        let limit = limit.unwrap_or(100).min(100) as usize;
+        let indices = self
+            .state
+            .maps
+            .indices()
+            .await
+            .expect("Failed to get map indices");
+
+        let mut maps = Vec::new();
+        for map_id in indices.into_iter().take(limit) {
+            if let Some(entry) = self.load_map(&map_id).await {
+                maps.push(entry);
+            }
+        }
+        maps
+    }
+
+    async fn map(&self, map_id: String) -> Option<MapEntryData> {
+        self.load_map(&map_id).await
+    }
+
+    /// Maps that take turns as the featured map, one week each
+    async fn featured_maps(&self) -> Vec<String> {
+        self.state.featured_maps.get().clone()
+    }
+
+    /// This week's featured map
+    async fn featured_map(&self) -> Option<MapEntryData> {
+        let now = self.runtime.system_time().micros();
+        let map_id = featured_map(self.state.featured_maps.get(), now)?.clone();
+        self.load_map(&map_id).await
+    }
+
+    /// This week's challenge mutators (`createGame(weeklyChallenge: true)` plays them)
+    async fn weekly_challenge(&self) -> Vec<String> {
+        let now = self.runtime.system_time().micros();
+        weekly_challenge(now)
+            .iter()
+            .map(|mutator| format!("{:?}", mutator))
+            .collect()
+    }
+
     // ===== Room Info Queries =====
 
     async fn room_info(&self) -> RoomInfoData {
@@ -358,6 +546,50 @@ impl QueryRoot {
     }
 }
 
+// ===== Query Helpers =====
+
+impl QueryRoot {
+    async fn load_map(&self, map_id: &String) -> Option<MapEntryData> {
+        self.state
+            .maps
+            .get(map_id)
+            .await
+            .expect("Failed to get map")
+            .map(|entry| MapEntryData::new(map_id.clone(), entry))
+    }
+
+    /// All towers with effective stats recomputed from current auras and mutators
+    async fn load_towers(&self) -> Vec<Tower> {
+        let indices = self
+            .state
+            .towers
+            .indices()
+            .await
+            .expect("Failed to get tower indices");
+
+        let mut towers = Vec::new();
+        for id in indices {
+            if let Some(tower) = self
+                .state
+                .towers
+                .get(&id)
+                .await
+                .expect("Failed to get tower")
+            {
+                towers.push(tower);
+            }
+        }
+        apply_auras(&mut towers);
+        let mutators = self
+            .state
+            .game_config
+            .get()
+            .mutators_for_wave(*self.state.wave_number.get());
+        apply_tower_mutators(&mut towers, &mutators);
+        towers
+    }
+}
+
 // ===== GraphQL Response Types =====
 
 #[derive(SimpleObject)]
@@ -366,10 +598,29 @@ pub struct TowerData {
     pub position: Vec<i32>,
     pub tower_type: String,
     pub level: i32,
+    pub specialization: Option<Specialization>,
+    pub is_support: bool,
+    pub targets_ground: bool,
+    pub targets_air: bool,
+    pub base_damage: i32,
+    pub base_range: i32,
+    pub base_fire_rate_ms: String,
+    /// Ground the tower stands on (e.g. "Rune" adds damage)
+    pub terrain: String,
+    /// Effective stats, including support auras and terrain
     pub damage: i32,
+    pub damage_type: String,
     pub range: i32,
     pub fire_rate_ms: String,
+    pub target_priority: TargetPriority,
+    pub chain_targets: i32,
+    pub splash_radius: f64,
+    pub aura_strength: i32,
+    pub gold_income: String,
+    pub crit_chance: f64,
     pub total_damage_dealt: String,
+    /// Game time until which a boss has switched the tower off
+    pub disabled_until_micros: String,
 }
 
 impl From<Tower> for TowerData {
@@ -379,10 +630,26 @@ impl From<Tower> for TowerData {
             position: vec![tower.position.0 as i32, tower.position.1 as i32],
             tower_type: format!("{:?}", tower.tower_type),
             level: tower.level as i32,
+            specialization: tower.specialization,
+            is_support: tower.tower_type.is_support(),
+            targets_ground: tower.tower_type.targets_ground(),
+            targets_air: tower.tower_type.targets_air(),
+            base_damage: tower.base_stats.damage as i32,
+            base_range: tower.base_stats.range as i32,
+            base_fire_rate_ms: tower.base_stats.fire_rate_ms.to_string(),
+            terrain: format!("{:?}", tower.terrain),
             damage: tower.stats.damage as i32,
+            damage_type: format!("{:?}", tower.stats.damage_type),
             range: tower.stats.range as i32,
             fire_rate_ms: tower.stats.fire_rate_ms.to_string(),
+            target_priority: tower.target_priority,
+            chain_targets: tower.stats.chain_targets as i32,
+            splash_radius: milli_to_f64(tower.stats.splash_radius as i64),
+            aura_strength: tower.stats.aura_strength as i32,
+            gold_income: tower.stats.gold_income.to_string(),
+            crit_chance: milli_to_f64(tower.stats.crit_chance_permille as i64),
             total_damage_dealt: tower.total_damage_dealt.to_string(),
+            disabled_until_micros: tower.disabled_until_micros.to_string(),
         }
     }
 }
@@ -393,11 +660,24 @@ pub struct EnemyData {
     pub enemy_type: String,
     pub position: Vec<f64>,
     pub path_index: i32,
+    /// Map lane the enemy follows
+    pub lane: i32,
     pub health: i32,
     pub max_health: i32,
     pub speed: f64,
     pub gold_reward: String,
+    /// "Ground" or "Air"
+    pub movement: String,
+    pub dodge_chance: f64,
     pub slow_multiplier: f64,
+    pub armor: i32,
+    pub shield: i32,
+    /// Special ability, e.g. "Heal", "Shield", "Split" or "Regenerate"
+    pub ability: Option<String>,
+    pub resistances: Vec<ResistanceData>,
+    pub status_effects: Vec<StatusEffectData>,
+    /// Boss phases entered so far (always 0 for other enemies)
+    pub boss_phase: i32,
 }
 
 impl From<Enemy> for EnemyData {
@@ -405,17 +685,65 @@ impl From<Enemy> for EnemyData {
         Self {
             id: enemy.id.to_string(),
             enemy_type: format!("{:?}", enemy.enemy_type),
-            position: vec![enemy.position.0 as f64, enemy.position.1 as f64],
+            // Fixed-point milli-units are converted to floats for clients only
+            position: vec![
+                milli_to_f64(enemy.position.0 as i64),
+                milli_to_f64(enemy.position.1 as i64),
+            ],
             path_index: enemy.path_index as i32,
+            lane: enemy.lane as i32,
             health: enemy.health as i32,
             max_health: enemy.max_health as i32,
-            speed: enemy.speed as f64,
+            speed: milli_to_f64(enemy.speed as i64),
             gold_reward: enemy.gold_reward.to_string(),
-            slow_multiplier: enemy.slow_multiplier as f64,
+            movement: format!("{:?}", enemy.movement),
+            dodge_chance: milli_to_f64(enemy.dodge_chance_permille as i64),
+            slow_multiplier: milli_to_f64(enemy.slow_multiplier() as i64),
+            armor: enemy.armor as i32,
+            shield: enemy.shield as i32,
+            ability: enemy.ability.map(|ability| match ability {
+                EnemyAbility::Heal { .. } => "Heal".to_string(),
+                EnemyAbility::Shield { .. } => "Shield".to_string(),
+                EnemyAbility::Split { .. } => "Split".to_string(),
+                EnemyAbility::Regenerate { .. } => "Regenerate".to_string(),
+            }),
+            resistances: DamageType::ALL
+                .iter()
+                .map(|damage_type| ResistanceData {
+                    damage_type: format!("{:?}", damage_type),
+                    multiplier: milli_to_f64(enemy.resistances.multiplier(*damage_type) as i64),
+                })
+                .collect(),
+            status_effects: enemy
+                .status_effects
+                .iter()
+                .map(|effect| StatusEffectData {
+                    kind: format!("{:?}", effect.kind),
+                    magnitude: effect.magnitude as i32,
+                    remaining_ms: (effect.remaining_micros / 1000).to_string(),
+                    source_tower_id: effect.source_tower_id.to_string(),
+                })
+                .collect(),
+            boss_phase: enemy.boss_phase as i32,
         }
     }
 }
 
+#[derive(SimpleObject)]
+pub struct ResistanceData {
+    pub damage_type: String,
+    /// Damage taken multiplier (1.0 = normal)
+    pub multiplier: f64,
+}
+
+#[derive(SimpleObject)]
+pub struct StatusEffectData {
+    pub kind: String,
+    pub magnitude: i32,
+    pub remaining_ms: String,
+    pub source_tower_id: String,
+}
+
 #[derive(SimpleObject)]
 pub struct PlayerData {
     pub owner: String,
@@ -458,6 +786,9 @@ pub struct LeaderboardEntryData {
     pub player: String,
     pub chain_id: String,
     pub highest_wave: String,
+    pub highest_endless_wave: String,
+    pub difficulty_waves: Vec<DifficultyWaveData>,
+    pub endless_difficulty_waves: Vec<DifficultyWaveData>,
     pub total_kills: String,
     pub total_games: String,
     pub total_damage: String,
@@ -470,6 +801,11 @@ impl From<LeaderboardEntry> for LeaderboardEntryData {
             player: format!("{:?}", entry.player),
             chain_id: format!("{:?}", entry.chain_id),
             highest_wave: entry.highest_wave.to_string(),
+            highest_endless_wave: entry.highest_endless_wave.to_string(),
+            difficulty_waves: DifficultyWaveData::list(&entry.highest_wave_by_difficulty),
+            endless_difficulty_waves: DifficultyWaveData::list(
+                &entry.highest_endless_wave_by_difficulty,
+            ),
             total_kills: entry.total_kills.to_string(),
             total_games: entry.total_games.to_string(),
             total_damage: entry.total_damage.to_string(),
@@ -478,13 +814,128 @@ impl From<LeaderboardEntry> for LeaderboardEntryData {
     }
 }
 
+/// Best wave on one difficulty preset
+#[derive(SimpleObject)]
+pub struct DifficultyWaveData {
+    pub difficulty: String,
+    pub highest_wave: i32,
+}
+
+impl DifficultyWaveData {
+    fn list(waves: &BTreeMap<Difficulty, u32>) -> Vec<Self> {
+        waves
+            .iter()
+            .map(|(difficulty, wave)| Self {
+                difficulty: format!("{:?}", difficulty),
+                highest_wave: *wave as i32,
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ProjectileData {
+    pub id: String,
+    pub tower_id: String,
+    pub tower_type: String,
+    pub target_id: String,
+    /// "Homing" or "Lobbed"
+    pub motion: String,
+    pub position: Vec<f64>,
+    /// Where a lobbed shell will land
+    pub target_position: Vec<f64>,
+    pub speed: f64,
+}
+
+impl From<Projectile> for ProjectileData {
+    fn from(projectile: Projectile) -> Self {
+        Self {
+            id: projectile.id.to_string(),
+            tower_id: projectile.tower_id().to_string(),
+            tower_type: format!("{:?}", projectile.source.tower_type),
+            target_id: projectile.target_id.to_string(),
+            motion: format!("{:?}", projectile.motion),
+            position: vec![
+                milli_to_f64(projectile.position.0 as i64),
+                milli_to_f64(projectile.position.1 as i64),
+            ],
+            target_position: vec![
+                milli_to_f64(projectile.target_position.0 as i64),
+                milli_to_f64(projectile.target_position.1 as i64),
+            ],
+            speed: milli_to_f64(projectile.speed as i64),
+        }
+    }
+}
+
 #[derive(SimpleObject)]
 pub struct GridData {
     pub width: i32,
     pub height: i32,
+    /// Lane 0's ground path
     pub path: Vec<Vec<i32>>,
+    /// Waypoints flying enemies on lane 0 follow
+    pub air_path: Vec<Vec<i32>>,
+    /// Lane 0's spawn point
     pub spawn_point: Vec<i32>,
+    /// Every lane, indexed like wave script groups
+    pub lanes: Vec<LaneData>,
     pub base_point: Vec<i32>,
+    /// Tiles that aren't plain grass
+    pub terrain: Vec<TerrainTileData>,
+    /// Tiles no tower may be built on
+    pub unbuildable: Vec<Vec<i32>>,
+    /// When set, towers may only be built on these tiles
+    pub build_pads: Option<Vec<Vec<i32>>>,
+    pub decorations: Vec<DecorationData>,
+}
+
+#[derive(SimpleObject)]
+pub struct LaneData {
+    pub spawn_point: Vec<i32>,
+    pub path: Vec<Vec<i32>>,
+    /// Waypoints flying enemies follow
+    pub air_path: Vec<Vec<i32>>,
+}
+
+impl From<&Lane> for LaneData {
+    fn from(lane: &Lane) -> Self {
+        Self {
+            spawn_point: tile_data(lane.spawn_point),
+            path: tiles_data(&lane.path),
+            air_path: tiles_data(&lane.air_path),
+        }
+    }
+}
+
+fn tile_data((x, y): (u8, u8)) -> Vec<i32> {
+    vec![x as i32, y as i32]
+}
+
+fn tiles_data<'a>(tiles: impl IntoIterator<Item = &'a (u8, u8)>) -> Vec<Vec<i32>> {
+    tiles.into_iter().copied().map(tile_data).collect()
+}
+
+#[derive(SimpleObject)]
+pub struct DecorationData {
+    /// Free-form scenery kind, e.g. "tree"
+    pub kind: String,
+    pub position: Vec<i32>,
+}
+
+#[derive(SimpleObject)]
+pub struct TerrainTileData {
+    pub position: Vec<i32>,
+    /// "Mud", "Road", "Bridge", "HighGround", "Water", "Rock" or "Rune"
+    pub terrain: String,
+    /// Ground speed multiplier on this tile
+    pub speed: f64,
+    pub buildable: bool,
+    /// Tower types that may be built here
+    pub allowed_towers: Vec<String>,
+    /// Extra tiles of range for towers here
+    pub range_bonus: i32,
+    pub damage_bonus_percent: i32,
 }
 
 #[derive(SimpleObject)]
@@ -494,6 +945,49 @@ pub struct GameConfigData {
     pub starting_gold: String,
     pub base_health: i32,
     pub max_towers: i32,
+    pub wave_script_name: Option<String>,
+    pub final_wave: Option<i32>,
+    pub projectiles: bool,
+    pub mazing: bool,
+    /// Registry map the game is played on (the classic map when unset)
+    pub map_id: Option<String>,
+    /// Seed of the game's procedural map, if it has one
+    pub map_seed: Option<String>,
+    pub difficulty: String,
+    /// Game-wide mutators
+    pub mutators: Vec<String>,
+    /// Week of the weekly challenge this game plays, if any
+    pub challenge_week: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct MapEntryData {
+    pub map_id: String,
+    pub name: String,
+    pub author: String,
+    pub official: bool,
+    pub submitted_at: String,
+    pub width: i32,
+    pub height: i32,
+    pub lane_count: i32,
+    /// Full map definition as JSON
+    pub map_json: String,
+}
+
+impl MapEntryData {
+    fn new(map_id: String, entry: MapEntry) -> Self {
+        Self {
+            map_id,
+            name: entry.map.name.clone(),
+            author: format!("{:?}", entry.author),
+            official: entry.official,
+            submitted_at: entry.submitted_at.to_string(),
+            width: entry.map.width as i32,
+            height: entry.map.height as i32,
+            lane_count: entry.map.lanes.len() as i32,
+            map_json: entry.map.to_json(),
+        }
+    }
 }
 
 #[derive(SimpleObject)]
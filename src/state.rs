@@ -1,6 +1,7 @@
 use linera_sdk::linera_base_types::{AccountOwner, ChainId};
 use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tower_defense_abi::*;
 
 /// Main application state using Linera's View system
@@ -59,9 +60,36 @@ pub struct TowerDefenseState {
     /// Active enemies (indexed by enemy ID)
     pub enemies: MapView<u64, Enemy>,
 
+    /// Enemies of the current wave waiting to spawn, in spawn order
+    pub pending_spawns: RegisterView<Vec<ScheduledSpawn>>,
+
+    /// Time since the current wave started (drives the spawn schedule)
+    pub wave_elapsed_micros: RegisterView<u64>,
+
+    /// Next enemy ID to assign (unique across waves)
+    pub next_enemy_id: RegisterView<u64>,
+
+    /// Tower shots in flight (indexed by projectile ID)
+    pub projectiles: MapView<u64, Projectile>,
+
+    /// Next projectile ID to assign
+    pub next_projectile_id: RegisterView<u64>,
+
+    /// Seed of this game's random rolls, derived from the game ID at creation
+    pub game_seed: RegisterView<u64>,
+
+    /// Random generator state, advanced by every roll
+    pub rng: RegisterView<GameRng>,
+
+    /// Create request waiting for its map from the master chain
+    pub pending_game: RegisterView<Option<PendingGame>>,
+
     /// Players in this game
     pub players: MapView<AccountOwner, PlayerGameStats>,
 
+    /// Simulation clock, advanced by game ticks (drives tower cooldowns)
+    pub sim_time_micros: RegisterView<u64>,
+
     /// Game tick counter (for timeout detection)
     pub game_tick_count: RegisterView<u64>,
 
@@ -90,6 +118,12 @@ pub struct TowerDefenseState {
 
     /// Season configuration
     pub season: RegisterView<Season>,
+
+    /// Registered maps (indexed by map ID)
+    pub maps: MapView<String, MapEntry>,
+
+    /// Maps that take turns as the featured map, one week each
+    pub featured_maps: RegisterView<Vec<String>>,
 }
 
 /// User status on their personal chain
@@ -115,6 +149,7 @@ pub enum GameMode {
     CoOp,        // All players share lives, work together
     Race,        // First to wave 20 wins
     HighScore,   // Highest score after 10 waves wins
+    Endless,     // Waves never stop; survive as long as possible
 }
 
 impl Default for GameMode {
@@ -253,7 +288,7 @@ impl MultiplayerGame {
     pub fn new(game_id: String, mode: GameMode, max_players: u8, host: AccountOwner) -> Self {
         let wave_sync_mode = match mode {
             GameMode::Versus | GameMode::Race => WaveSyncMode::Independent,
-            GameMode::CoOp | GameMode::HighScore => WaveSyncMode::Synchronized,
+            GameMode::CoOp | GameMode::HighScore | GameMode::Endless => WaveSyncMode::Synchronized,
         };
 
         Self {
@@ -289,12 +324,39 @@ pub struct LeaderboardEntry {
     pub player: AccountOwner,
     pub chain_id: ChainId,
     pub highest_wave: u32,
+    /// Most waves survived in a single endless game
+    pub highest_endless_wave: u32,
+    /// Highest wave reached on each difficulty (endless games excluded)
+    pub highest_wave_by_difficulty: BTreeMap<Difficulty, u32>,
+    /// Most waves survived in a single endless game on each difficulty
+    pub highest_endless_wave_by_difficulty: BTreeMap<Difficulty, u32>,
     pub total_kills: u64,
     pub total_games: u32,
     pub total_damage: u64,
     pub last_updated: u64,
 }
 
+/// Registered map (stored on master chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapEntry {
+    pub map: MapDefinition,
+    pub author: AccountOwner,
+    /// Submitted by the admin rather than the community
+    pub official: bool,
+    pub submitted_at: u64,
+}
+
+/// Create game request parked until its map arrives (stored on game chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingGame {
+    pub mode: GameMode,
+    pub max_players: u8,
+    pub is_private: bool,
+    pub player_name: String,
+    pub user_chain: ChainId,
+    pub settings: GameSettings,
+}
+
 /// Season configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Season {
@@ -321,6 +383,16 @@ pub struct PlayerScore {
     pub wave_reached: u32,
     pub kills: u32,
     pub damage_dealt: u64,
+    /// Seed the game was played with, to replay it exactly
+    pub seed: u64,
+    pub endless: bool,
+    /// Waves cleared before the game ended
+    pub waves_survived: u32,
+    pub difficulty: Difficulty,
+    /// Game-wide mutators the game was played with
+    pub mutators: Vec<Mutator>,
+    /// Weekly challenge the game was played as, if any
+    pub challenge_week: Option<u64>,
 }
 
 #[cfg(test)]
@@ -73,20 +73,20 @@ fn test_enemy_takes_damage() {
 
 #[test]
 fn test_wave_generation() {
-    let enemies = wave::generate_wave(1, (0, 10));
+    let enemies = wave::generate_wave(1, (0, 10), &mut GameRng::new(1));
 
     // Wave 1 should have enemies
     assert!(!enemies.is_empty());
 
     // All enemies should spawn at spawn point
     for enemy in &enemies {
-        assert_eq!(enemy.position, (0.0, 10.0));
+        assert_eq!(enemy.position, (0, 10_000)); // Milli-tiles
     }
 }
 
 #[test]
 fn test_boss_wave() {
-    let enemies = wave::generate_wave(10, (0, 10));
+    let enemies = wave::generate_wave(10, (0, 10), &mut GameRng::new(1));
 
     // Wave 10 should have a boss
     let has_boss = enemies.iter().any(|e| e.enemy_type == EnemyType::Boss);
@@ -96,14 +96,15 @@ fn test_boss_wave() {
 #[test]
 fn test_economy_rewards() {
     // Test kill rewards
-    let wave1_reward = economy::calculate_kill_reward(EnemyType::BasicScout, 1);
-    let wave10_reward = economy::calculate_kill_reward(EnemyType::BasicScout, 10);
+    let wave1_reward = economy::calculate_kill_reward(EnemyType::BasicScout, 1, Difficulty::Normal);
+    let wave10_reward =
+        economy::calculate_kill_reward(EnemyType::BasicScout, 10, Difficulty::Normal);
 
     // Later waves give more gold
     assert!(wave10_reward > wave1_reward);
 
     // Boss gives much more reward
-    let boss_reward = economy::calculate_kill_reward(EnemyType::Boss, 1);
+    let boss_reward = economy::calculate_kill_reward(EnemyType::Boss, 1, Difficulty::Normal);
     assert!(boss_reward > wave1_reward);
 }
 
@@ -158,7 +159,7 @@ fn test_combat_targeting() {
         },
     ];
 
-    let target = find_target(&tower, &enemies);
+    let target = find_target(&tower, &enemies, &Grid::new());
 
     // Should target enemy furthest along path BUT in range
     assert!(target.is_some());